@@ -1,76 +1,225 @@
 use color_eyre::eyre::Result;
-use crossterm::event::{self};
+use crossterm::event::{self, Event};
 use ratatui::DefaultTerminal;
 
 use crate::config;
+use crate::openstack::category::Category;
+use crate::openstack::error::Report;
+use crate::openstack::retry::RetryPolicy;
+use crate::openstack::scope::Scope;
 use crate::openstack::server::Server;
+use crate::openstack::session::{self, Session};
 use crate::openstack::token;
+use crate::profile::{Profile, ProfilePicker};
+use crate::project::ProjectPicker;
 use crate::state;
+use crate::totp::TotpPrompt;
 
 pub struct App {
-    token: String,
-    endpoints: Vec<token::Endpoint>,
+    session: Option<Session>,
+    // profile of the current session
+    profile: Profile,
+    profiles: ProfilePicker,
     config: config::Config,
+    totp: Option<TotpPrompt>,
+    projects: Option<ProjectPicker>,
     state: state::AppState,
 }
 
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl App {
     pub fn new() -> Self {
-        let config = config::load();
-        let mut state = state::AppState::Loading;
-        if config.is_valid() {
-            state = state::AppState::IssueToken {
-                userid: config.userid.clone(),
-                password: config.password.clone(),
-                tenantid: config.tenantid.clone(),
-                identity_url: config.identity_url.clone(),
-            }
-        }
         Self {
-            token: String::new(),
-            endpoints: Vec::new(),
-            config: config,
-            state: state,
+            session: None,
+            profile: Profile::default(),
+            profiles: ProfilePicker::load(),
+            config: config::Config::default(),
+            totp: None,
+            projects: None,
+            state: state::AppState::Profiles,
         }
     }
 
     pub async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
         while self.is_running() {
             match self.state {
-                state::AppState::Loading => {
-                    let _ = terminal.draw(|frame| self.config.render(frame));
+                state::AppState::Profiles => {
+                    let _ = terminal.draw(|frame| self.profiles.render(frame));
                     self.state = self
-                        .config
+                        .profiles
                         .handle_events(event::read()?.as_key_press_event());
+                    if let Some(config) = self.profiles.take_selected() {
+                        self.config = config;
+                    }
+                }
+                state::AppState::Loading => {
+                    let _ = terminal.draw(|frame| self.config.render(frame));
+                    match event::read()? {
+                        Event::Paste(text) => self.config.paste(&text),
+                        event => self.state = self.config.handle_events(event.as_key_press_event()),
+                    }
+                    if self.state == state::AppState::Profiles {
+                        self.profiles.reload();
+                    }
+                }
+                state::AppState::TestConnection(ref url) => {
+                    // show "Testing ..." while the request is in flight
+                    let _ = terminal.draw(|frame| self.config.render(frame));
+                    let result = match session::client(&self.config.profile()) {
+                        Ok(client) => token::check_identity(&client, url).await,
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(version) => {
+                            self.config.message =
+                                format!("Connected to {}, Identity API {}", url, version)
+                        }
+                        Err(e) => {
+                            self.config.message.clear();
+                            self.config.error = Some(Report::new("Connection test", &e));
+                        }
+                    }
+                    self.state = state::AppState::Loading;
                 }
-                state::AppState::IssueToken {
-                    ref userid,
-                    ref password,
-                    ref tenantid,
-                    ref identity_url,
-                } => {
+                state::AppState::IssueToken(ref profile) => {
+                    let client = match session::client(profile) {
+                        Ok(client) => client,
+                        Err(e) => {
+                            self.config.error = Some(Report::new("Issuing the token", &e));
+                            self.state = state::AppState::Loading;
+                            continue;
+                        }
+                    };
                     match token::issue_token(
-                        userid.clone(),
-                        password.clone(),
-                        tenantid.clone(),
-                        identity_url.clone(),
+                        &client,
+                        &RetryPolicy::from(profile.as_ref()),
+                        &profile.credentials,
                     )
                     .await
                     {
-                        Ok(res) => {
-                            self.token = res.token;
-                            self.endpoints = res.endpoints;
-                            self.state = state::AppState::Server;
+                        Ok(res) => self.start_session(profile.as_ref().clone(), client, res),
+                        Err(e) => match e.downcast::<token::AuthReceipt>() {
+                            Ok(receipt) if receipt.accepts_totp() => {
+                                self.totp =
+                                    Some(TotpPrompt::new(profile.as_ref().clone(), receipt));
+                                self.state = state::AppState::Totp;
+                            }
+                            Ok(receipt) => {
+                                let e = anyhow::Error::from(receipt);
+                                self.config.error = Some(Report::new("Issuing the token", &e));
+                                self.state = state::AppState::Loading;
+                            }
+                            Err(e) => {
+                                self.config.error = Some(Report::new("Issuing the token", &e));
+                                self.state = state::AppState::Loading;
+                            }
+                        },
+                    }
+                }
+                state::AppState::Totp => {
+                    let Some(prompt) = self.totp.as_mut() else {
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    let _ = terminal.draw(|frame| prompt.render(frame));
+                    match event::read()? {
+                        Event::Paste(text) => prompt.paste(&text),
+                        event => self.state = prompt.handle_events(event.as_key_press_event()),
+                    }
+                }
+                state::AppState::VerifyTotp => {
+                    let Some(prompt) = self.totp.take() else {
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    let result = match session::client(&prompt.profile) {
+                        Ok(client) => token::issue_token_with_totp(
+                            &client,
+                            &RetryPolicy::from(&prompt.profile),
+                            &prompt.profile.credentials,
+                            &prompt.receipt.receipt,
+                            prompt.passcode.value(),
+                        )
+                        .await
+                        .map(|res| (client, res)),
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok((client, res)) => self.start_session(prompt.profile, client, res),
+                        Err(e) => {
+                            // the receipt stays valid for a while, so let the user retry
+                            let mut prompt = prompt;
+                            prompt.passcode.clear();
+                            prompt.message = format!("Error verifying passcode: {}", e);
+                            self.totp = Some(prompt);
+                            self.state = state::AppState::Totp;
                         }
+                    }
+                }
+                state::AppState::Projects => {
+                    let Some(picker) = self.projects.as_mut() else {
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    let _ = terminal.draw(|frame| picker.render(frame));
+                    self.state = picker.handle_events(event::read()?.as_key_press_event());
+                }
+                state::AppState::Rescope => {
+                    let project = self.projects.take().and_then(|p| p.selected().cloned());
+                    let (Some(session), Some(project)) = (self.session.clone(), project) else {
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    // for this session only, so re-issued tokens keep the project
+                    let mut profile = self.profile.clone();
+                    profile.credentials.scope = Scope::Project;
+                    profile.credentials.tenantid = project.id;
+                    match session.rescope(&profile.credentials).await {
+                        Ok(res) => self.start_session(profile, session.client(), res),
                         Err(e) => {
-                            self.config.message = format!("Error issuing token: {}", e);
+                            self.config.error = Some(Report::new("Switching to the project", &e));
                             self.state = state::AppState::Loading;
                         }
                     }
                 }
                 state::AppState::Server => {
-                    let server = Server::new("http://localhost:5000".to_string());
+                    let Some(session) = self.session.clone() else {
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    if !self.profile.credentials.is_project_scoped() {
+                        self.pick_project(&session).await;
+                        continue;
+                    }
+                    let endpoints = session.endpoints();
+                    let Some(endpoint) = token::find_endpoint(
+                        &endpoints,
+                        Category::Compute,
+                        self.profile.interface,
+                        &self.profile.region,
+                    ) else {
+                        self.config.message = format!(
+                            "No {} compute endpoint found in the service catalog{}",
+                            self.profile.interface.as_str(),
+                            if self.profile.region.is_empty() {
+                                String::new()
+                            } else {
+                                format!(" for region {}", self.profile.region)
+                            }
+                        );
+                        self.state = state::AppState::Loading;
+                        continue;
+                    };
+                    let server = Server::new(endpoint.url.clone(), session, self.profile.clone());
                     self.state = server.run(&mut terminal).await?;
+                    if self.state == state::AppState::Profiles {
+                        self.profiles.reload();
+                    }
                 }
                 state::AppState::Quit => {
                     return Ok(());
@@ -81,6 +230,47 @@ impl App {
         Ok(())
     }
 
+    fn start_session(
+        &mut self,
+        profile: Profile,
+        client: reqwest::Client,
+        res: token::TokenResponse,
+    ) {
+        self.session = Some(Session::new(
+            client,
+            RetryPolicy::from(&profile),
+            profile.credentials.clone(),
+            res,
+        ));
+        self.profile = profile;
+        self.state = state::AppState::Server;
+    }
+
+    // The token works for Keystone but not for Nova, so offer the user's projects
+    async fn pick_project(&mut self, session: &Session) {
+        let identity_url = &self.profile.credentials.identity_url;
+        self.state = state::AppState::Loading;
+        match token::list_projects(session, identity_url).await {
+            Ok(projects) if projects.is_empty() => {
+                self.config.message = format!(
+                    "The server list needs a project scoped token, but {} has no project",
+                    self.profile.name
+                );
+            }
+            Ok(projects) => {
+                let message = format!(
+                    "Signed in {} scoped, pick a project for the server list",
+                    self.profile.credentials.scope.as_str()
+                );
+                self.projects = Some(ProjectPicker::new(projects, message));
+                self.state = state::AppState::Projects;
+            }
+            Err(e) => {
+                self.config.error = Some(Report::new("Listing the projects", &e));
+            }
+        }
+    }
+
     fn is_running(&self) -> bool {
         self.state != state::AppState::Quit
     }
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::openstack::auth_method::AuthMethod;
+use crate::openstack::interface::Interface;
+use crate::openstack::scope::Scope;
+
+// Where a config value came from, shown next to the field in the TUI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Profile,
+    CloudsYaml { path: PathBuf, cloud: String },
+    SecureYaml { path: PathBuf, cloud: String },
+    Env(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Profile => write!(f, "profiles.json"),
+            Source::CloudsYaml { path, cloud } | Source::SecureYaml { path, cloud } => {
+                write!(f, "{} ({})", path.display(), cloud)
+            }
+            Source::Env(name) => write!(f, "${}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    AuthMethod,
+    UserId,
+    Username,
+    UserDomainName,
+    UserDomainId,
+    Password,
+    Scope,
+    TenantId,
+    ProjectName,
+    ProjectDomainName,
+    ProjectDomainId,
+    DomainId,
+    DomainName,
+    ApplicationCredentialId,
+    ApplicationCredentialName,
+    ApplicationCredentialSecret,
+    IdentityUrl,
+    Region,
+    Interface,
+    CaBundle,
+    Insecure,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub key: Key,
+    // choices use the same names as the config form options
+    pub value: String,
+    pub source: Source,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CloudsFile {
+    #[serde(default)]
+    clouds: HashMap<String, Cloud>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Cloud {
+    auth_type: Option<String>,
+    #[serde(default)]
+    auth: CloudAuth,
+    region_name: Option<String>,
+    interface: Option<String>,
+    cacert: Option<String>,
+    // false turns off certificate verification
+    verify: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct CloudAuth {
+    auth_url: Option<String>,
+    user_id: Option<String>,
+    username: Option<String>,
+    user_domain_name: Option<String>,
+    user_domain_id: Option<String>,
+    password: Option<String>,
+    #[serde(alias = "tenant_id")]
+    project_id: Option<String>,
+    #[serde(alias = "tenant_name")]
+    project_name: Option<String>,
+    project_domain_name: Option<String>,
+    project_domain_id: Option<String>,
+    domain_id: Option<String>,
+    domain_name: Option<String>,
+    application_credential_id: Option<String>,
+    application_credential_name: Option<String>,
+    application_credential_secret: Option<String>,
+}
+
+// Directories searched for clouds.yaml and secure.yaml, highest priority first
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".")];
+    if let Some(config) = dirs::config_dir() {
+        dirs.push(config.join("openstack"));
+    }
+    dirs.push(PathBuf::from("/etc/openstack"));
+    dirs
+}
+
+fn find_file(name: &str, override_var: &str) -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(override_var) {
+        return Some(PathBuf::from(path));
+    }
+    search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.exists())
+}
+
+// Settings from clouds.yaml, secure.yaml and OS_* variables, lowest priority first,
+// and why a file that was found could not be used
+pub fn load() -> (Vec<Setting>, Vec<String>) {
+    let mut settings = Vec::new();
+    let mut errors = Vec::new();
+
+    if let Ok(cloud) = std::env::var("OS_CLOUD") {
+        for (name, override_var, secure) in [
+            ("clouds.yaml", "OS_CLIENT_CONFIG_FILE", false),
+            ("secure.yaml", "OS_CLIENT_SECURE_FILE", true),
+        ] {
+            let Some(path) = find_file(name, override_var) else {
+                continue;
+            };
+            match read_cloud_file(&path, &cloud, secure) {
+                Ok(found) => settings.extend(found),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    settings.extend(env_settings(|name| std::env::var(name).ok()));
+    (settings, errors)
+}
+
+fn read_cloud_file(path: &Path, cloud: &str, secure: bool) -> Result<Vec<Setting>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    cloud_settings(&content, cloud, path, secure)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn cloud_settings(
+    content: &str,
+    cloud: &str,
+    path: &Path,
+    secure: bool,
+) -> Result<Vec<Setting>, serde_yaml_ng::Error> {
+    let file: CloudsFile = serde_yaml_ng::from_str(content)?;
+    let Some(entry) = file.clouds.get(cloud) else {
+        return Ok(Vec::new());
+    };
+    let source = if secure {
+        Source::SecureYaml {
+            path: path.to_path_buf(),
+            cloud: cloud.to_string(),
+        }
+    } else {
+        Source::CloudsYaml {
+            path: path.to_path_buf(),
+            cloud: cloud.to_string(),
+        }
+    };
+
+    let auth = &entry.auth;
+    let texts = [
+        (Key::IdentityUrl, &auth.auth_url),
+        (Key::UserId, &auth.user_id),
+        (Key::Username, &auth.username),
+        (Key::UserDomainName, &auth.user_domain_name),
+        (Key::UserDomainId, &auth.user_domain_id),
+        (Key::Password, &auth.password),
+        (Key::TenantId, &auth.project_id),
+        (Key::ProjectName, &auth.project_name),
+        (Key::ProjectDomainName, &auth.project_domain_name),
+        (Key::ProjectDomainId, &auth.project_domain_id),
+        (Key::DomainId, &auth.domain_id),
+        (Key::DomainName, &auth.domain_name),
+        (
+            Key::ApplicationCredentialId,
+            &auth.application_credential_id,
+        ),
+        (
+            Key::ApplicationCredentialName,
+            &auth.application_credential_name,
+        ),
+        (
+            Key::ApplicationCredentialSecret,
+            &auth.application_credential_secret,
+        ),
+        (Key::Region, &entry.region_name),
+        (Key::CaBundle, &entry.cacert),
+    ];
+    let mut settings: Vec<Setting> = texts
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = value.as_ref().filter(|v| !v.is_empty())?;
+            Some(Setting {
+                key,
+                value: value.clone(),
+                source: source.clone(),
+            })
+        })
+        .collect();
+
+    if entry.verify == Some(false) {
+        settings.push(insecure(&source));
+    }
+
+    let project = auth.project_id.is_some() || auth.project_name.is_some();
+    let domain = auth.domain_id.is_some() || auth.domain_name.is_some();
+    settings.extend(typed_settings(
+        entry.auth_type.as_deref(),
+        entry.interface.as_deref(),
+        project,
+        domain,
+        &source,
+    ));
+    Ok(settings)
+}
+
+fn env_settings(get: impl Fn(&str) -> Option<String>) -> Vec<Setting> {
+    // the first variable that is set wins for each key
+    let vars: [(Key, &[&'static str]); 17] = [
+        (Key::IdentityUrl, &["OS_AUTH_URL"]),
+        (Key::UserId, &["OS_USER_ID"]),
+        (Key::Username, &["OS_USERNAME"]),
+        (Key::UserDomainName, &["OS_USER_DOMAIN_NAME"]),
+        (Key::UserDomainId, &["OS_USER_DOMAIN_ID"]),
+        (Key::Password, &["OS_PASSWORD"]),
+        (Key::TenantId, &["OS_PROJECT_ID", "OS_TENANT_ID"]),
+        (Key::ProjectName, &["OS_PROJECT_NAME", "OS_TENANT_NAME"]),
+        (Key::ProjectDomainName, &["OS_PROJECT_DOMAIN_NAME"]),
+        (Key::ProjectDomainId, &["OS_PROJECT_DOMAIN_ID"]),
+        (Key::DomainId, &["OS_DOMAIN_ID"]),
+        (Key::DomainName, &["OS_DOMAIN_NAME"]),
+        (
+            Key::ApplicationCredentialId,
+            &["OS_APPLICATION_CREDENTIAL_ID"],
+        ),
+        (
+            Key::ApplicationCredentialName,
+            &["OS_APPLICATION_CREDENTIAL_NAME"],
+        ),
+        (
+            Key::ApplicationCredentialSecret,
+            &["OS_APPLICATION_CREDENTIAL_SECRET"],
+        ),
+        (Key::Region, &["OS_REGION_NAME"]),
+        (Key::CaBundle, &["OS_CACERT"]),
+    ];
+    let lookup = |names: &[&'static str]| {
+        names
+            .iter()
+            .find_map(|name| get(name).filter(|v| !v.is_empty()).map(|v| (*name, v)))
+    };
+
+    let mut settings: Vec<Setting> = vars
+        .iter()
+        .filter_map(|(key, names)| {
+            let (name, value) = lookup(names)?;
+            Some(Setting {
+                key: *key,
+                value,
+                source: Source::Env(name),
+            })
+        })
+        .collect();
+
+    let has = |keys: &[Key]| settings.iter().any(|s| keys.contains(&s.key));
+    let project = has(&[Key::TenantId, Key::ProjectName]);
+    let domain = has(&[Key::DomainId, Key::DomainName]);
+    if let Some((name, auth_type)) = lookup(&["OS_AUTH_TYPE"]) {
+        settings.extend(typed_settings(
+            Some(&auth_type),
+            None,
+            false,
+            false,
+            &Source::Env(name),
+        ));
+    }
+    if let Some((name, value)) = lookup(&["OS_INSECURE"])
+        && matches!(value.to_lowercase().as_str(), "1" | "true" | "yes")
+    {
+        settings.push(insecure(&Source::Env(name)));
+    }
+    if let Some((name, interface)) = lookup(&["OS_INTERFACE", "OS_ENDPOINT_TYPE"]) {
+        settings.extend(typed_settings(
+            None,
+            Some(&interface),
+            false,
+            false,
+            &Source::Env(name),
+        ));
+    }
+    if project || domain {
+        let name = if project {
+            "OS_PROJECT_*"
+        } else {
+            "OS_DOMAIN_*"
+        };
+        settings.extend(typed_settings(
+            None,
+            None,
+            project,
+            domain,
+            &Source::Env(name),
+        ));
+    }
+    settings
+}
+
+fn insecure(source: &Source) -> Setting {
+    Setting {
+        key: Key::Insecure,
+        value: "yes".to_string(),
+        source: source.clone(),
+    }
+}
+
+// Settings that are not plain text: auth method, interface and the implied scope
+fn typed_settings(
+    auth_type: Option<&str>,
+    interface: Option<&str>,
+    project: bool,
+    domain: bool,
+    source: &Source,
+) -> Vec<Setting> {
+    let mut settings = Vec::new();
+    let method = match auth_type {
+        Some("password" | "v3password") => Some(AuthMethod::Password),
+        Some("v3applicationcredential" | "applicationcredential") => {
+            Some(AuthMethod::ApplicationCredential)
+        }
+        _ => None,
+    };
+    if let Some(method) = method {
+        settings.push(Setting {
+            key: Key::AuthMethod,
+            value: method.as_str().to_string(),
+            source: source.clone(),
+        });
+    }
+    if let Some(interface) = interface.and_then(Interface::from_name) {
+        settings.push(Setting {
+            key: Key::Interface,
+            value: interface.as_str().to_string(),
+            source: source.clone(),
+        });
+    }
+    // a project takes precedence, like the other OpenStack clients
+    let scope = if project {
+        Some(Scope::Project)
+    } else if domain {
+        Some(Scope::Domain)
+    } else {
+        None
+    };
+    if let Some(scope) = scope {
+        settings.push(Setting {
+            key: Key::Scope,
+            value: scope.as_str().to_string(),
+            source: source.clone(),
+        });
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOUDS_YAML: &str = r#"
+clouds:
+  devstack:
+    auth_type: password
+    region_name: RegionOne
+    interface: internal
+    auth:
+      auth_url: https://keystone.example.com/v3
+      username: demo
+      user_domain_name: Default
+      project_name: demo
+      project_domain_id: default
+  ci:
+    auth_type: v3applicationcredential
+    cacert: /etc/ssl/private-ca.pem
+    verify: false
+    auth:
+      auth_url: https://keystone.example.com/v3
+      application_credential_id: 0123abcd
+"#;
+
+    const SECURE_YAML: &str = r#"
+clouds:
+  devstack:
+    auth:
+      password: secret
+  ci:
+    auth:
+      application_credential_secret: appsecret
+"#;
+
+    fn find(settings: &[Setting], key: Key) -> Option<&Setting> {
+        // later settings win, like when they are applied in order
+        settings.iter().rev().find(|s| s.key == key)
+    }
+
+    #[test]
+    fn test_cloud_settings() {
+        let path = Path::new("clouds.yaml");
+        let settings = cloud_settings(CLOUDS_YAML, "devstack", path, false).unwrap();
+
+        assert_eq!(
+            find(&settings, Key::Username).map(|s| s.value.as_str()),
+            Some("demo")
+        );
+        assert_eq!(
+            find(&settings, Key::Region).map(|s| s.value.as_str()),
+            Some("RegionOne")
+        );
+        assert_eq!(
+            find(&settings, Key::Interface).map(|s| s.value.as_str()),
+            Some(Interface::Internal.as_str())
+        );
+        assert_eq!(
+            find(&settings, Key::Scope).map(|s| s.value.as_str()),
+            Some(Scope::Project.as_str())
+        );
+        assert!(find(&settings, Key::Password).is_none());
+        assert_eq!(
+            find(&settings, Key::Username).map(|s| s.source.to_string()),
+            Some("clouds.yaml (devstack)".to_string())
+        );
+
+        let secure =
+            cloud_settings(SECURE_YAML, "devstack", Path::new("secure.yaml"), true).unwrap();
+        assert_eq!(
+            find(&secure, Key::Password).map(|s| s.value.as_str()),
+            Some("secret")
+        );
+
+        let ci = cloud_settings(CLOUDS_YAML, "ci", path, false).unwrap();
+        assert_eq!(
+            find(&ci, Key::AuthMethod).map(|s| s.value.as_str()),
+            Some(AuthMethod::ApplicationCredential.as_str())
+        );
+        assert_eq!(
+            find(&ci, Key::CaBundle).map(|s| s.value.as_str()),
+            Some("/etc/ssl/private-ca.pem")
+        );
+        assert_eq!(
+            find(&ci, Key::Insecure).map(|s| s.value.as_str()),
+            Some("yes")
+        );
+        assert!(find(&settings, Key::Insecure).is_none());
+
+        assert!(
+            cloud_settings(CLOUDS_YAML, "missing", path, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_read_cloud_file_errors() {
+        let path = std::env::temp_dir().join(format!("clouds-{}.yaml", std::process::id()));
+        let error = read_cloud_file(&path, "devstack", false).unwrap_err();
+        assert!(error.starts_with("Failed to read"), "{}", error);
+
+        std::fs::write(&path, "clouds:\n  devstack: [unclosed").unwrap();
+        let error = read_cloud_file(&path, "devstack", false).unwrap_err();
+        assert!(error.starts_with("Failed to parse"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_env_settings() {
+        let env = HashMap::from([
+            ("OS_AUTH_URL", "https://keystone.example.com/v3"),
+            ("OS_USERNAME", "admin"),
+            ("OS_TENANT_NAME", "admin"),
+            ("OS_DOMAIN_NAME", ""),
+            ("OS_ENDPOINT_TYPE", "adminURL"),
+        ]);
+        let settings = env_settings(|name| env.get(name).map(|v| v.to_string()));
+
+        let project = find(&settings, Key::ProjectName).unwrap();
+        assert_eq!(project.value, "admin");
+        assert_eq!(project.source, Source::Env("OS_TENANT_NAME"));
+        assert_eq!(project.source.to_string(), "$OS_TENANT_NAME");
+        // empty variables count as unset
+        assert!(find(&settings, Key::DomainName).is_none());
+        assert_eq!(
+            find(&settings, Key::Interface).map(|s| s.value.as_str()),
+            Some(Interface::Admin.as_str())
+        );
+        assert_eq!(
+            find(&settings, Key::Scope).map(|s| s.value.as_str()),
+            Some(Scope::Project.as_str())
+        );
+    }
+}
@@ -1,333 +1,1030 @@
-use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use dirs;
+use color_eyre::eyre::{Result, eyre};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::Frame;
-use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Layout, Offset, Rect};
+use ratatui::layout::{Constraint, Layout};
 use ratatui::text::Line;
-use ratatui::widgets::{Paragraph, Widget};
+use ratatui::widgets::Paragraph;
 use ratatui_core::style::Stylize;
-use serde::{Deserialize, Serialize};
+use reqwest::Url;
+use std::collections::HashMap;
 
+use crate::clouds::{self, Key, Setting, Source};
+use crate::field::{Field, PasswordField, SelectField, StringField};
+use crate::openstack::auth_method::AuthMethod;
+use crate::openstack::column::Columns;
+use crate::openstack::error::Report;
+use crate::openstack::interface::Interface;
+use crate::openstack::microversion::Microversion;
+use crate::openstack::retry::RetryPolicy;
+use crate::openstack::scope::Scope;
+use crate::openstack::token::Credentials;
+use crate::profile::{DEFAULT_REFRESH_INTERVAL, Profile, Store};
+use crate::secret::{SecretStorage, SecretStore, Secrets};
 use crate::state;
 
+#[derive(Clone)]
 struct Fields {
+    name: StringField,
+    auth_method: SelectField,
     userid: StringField,
+    username: StringField,
+    user_domain_name: StringField,
+    user_domain_id: StringField,
     password: PasswordField,
+    scope: SelectField,
     tenantid: StringField,
+    project_name: StringField,
+    project_domain_name: StringField,
+    project_domain_id: StringField,
+    domain_id: StringField,
+    domain_name: StringField,
+    application_credential_id: StringField,
+    application_credential_name: StringField,
+    application_credential_secret: PasswordField,
+    secret_storage: SelectField,
+    passphrase: PasswordField,
     identity_url: StringField,
+    region: StringField,
+    interface: SelectField,
+    refresh_interval: StringField,
+    max_microversion: StringField,
+    ca_bundle: StringField,
+    insecure: SelectField,
+    max_retries: StringField,
+    retry_delay: StringField,
+
+    // where each value came from
+    sources: HashMap<Focus, Source>,
+    // server table layout, edited from the server view
+    columns: Columns,
 }
 
 impl Default for Fields {
     fn default() -> Self {
         Fields {
+            name: StringField::new("Profile Name".to_string()),
+            auth_method: SelectField::new("Auth Method".to_string(), auth_method_options()),
             userid: StringField::new("User ID".to_string()),
+            username: StringField::new("User Name".to_string()),
+            user_domain_name: StringField::new("User Domain Name".to_string()),
+            user_domain_id: StringField::new("User Domain ID".to_string()),
             password: PasswordField::new("Password".to_string()),
+            scope: SelectField::new("Scope".to_string(), scope_options()),
             tenantid: StringField::new("Tenant ID".to_string()),
+            project_name: StringField::new("Project Name".to_string()),
+            project_domain_name: StringField::new("Project Domain Name".to_string()),
+            project_domain_id: StringField::new("Project Domain ID".to_string()),
+            domain_id: StringField::new("Domain ID".to_string()),
+            domain_name: StringField::new("Domain Name".to_string()),
+            application_credential_id: StringField::new("Application Credential ID".to_string()),
+            application_credential_name: StringField::new(
+                "Application Credential Name".to_string(),
+            ),
+            application_credential_secret: PasswordField::new(
+                "Application Credential Secret".to_string(),
+            ),
+            secret_storage: SelectField::new("Store Secrets".to_string(), secret_storage_options()),
+            passphrase: PasswordField::new("Passphrase".to_string()),
             identity_url: StringField::new("Identity URL".to_string()),
+            region: StringField::new("Region".to_string()),
+            interface: SelectField::new("Interface".to_string(), interface_options()),
+            refresh_interval: refresh_interval_field(),
+            max_microversion: max_microversion_field(),
+            ca_bundle: StringField::new("CA Bundle".to_string()),
+            insecure: SelectField::new("Skip TLS Verify".to_string(), yes_no_options()),
+            max_retries: max_retries_field(),
+            retry_delay: retry_delay_field(),
+            sources: HashMap::new(),
+            columns: Columns::default(),
         }
     }
 }
 
-impl From<&Config> for Fields {
-    fn from(config: &Config) -> Self {
-        Fields {
-            userid: StringField {
-                label: "User ID".to_string(),
-                value: config.userid.clone(),
-            },
-            password: PasswordField {
-                label: "Password".to_string(),
-                display_value: "*".repeat(config.password.len()),
-                value: config.password.clone(),
-            },
-            tenantid: StringField {
-                label: "Tenant ID".to_string(),
-                value: config.tenantid.clone(),
-            },
-            identity_url: StringField {
-                label: "Identity URL".to_string(),
-                value: config.identity_url.clone(),
-            },
+impl From<&Profile> for Fields {
+    fn from(config: &Profile) -> Self {
+        let credentials = &config.credentials;
+        let mut fields = Fields::default();
+        fields.name.set_value(config.name.clone());
+        fields.auth_method.index = AuthMethod::ALL
+            .iter()
+            .position(|m| *m == credentials.method)
+            .unwrap_or_default();
+        fields.userid.set_value(credentials.userid.clone());
+        fields.username.set_value(credentials.username.clone());
+        fields
+            .user_domain_name
+            .set_value(credentials.user_domain_name.clone());
+        fields
+            .user_domain_id
+            .set_value(credentials.user_domain_id.clone());
+        fields.password.set_value(credentials.password.clone());
+        fields.scope.index = Scope::ALL
+            .iter()
+            .position(|s| *s == credentials.scope)
+            .unwrap_or_default();
+        fields.tenantid.set_value(credentials.tenantid.clone());
+        fields
+            .project_name
+            .set_value(credentials.project_name.clone());
+        fields
+            .project_domain_name
+            .set_value(credentials.project_domain_name.clone());
+        fields
+            .project_domain_id
+            .set_value(credentials.project_domain_id.clone());
+        fields.domain_id.set_value(credentials.domain_id.clone());
+        fields
+            .domain_name
+            .set_value(credentials.domain_name.clone());
+        fields
+            .application_credential_id
+            .set_value(credentials.application_credential_id.clone());
+        fields
+            .application_credential_name
+            .set_value(credentials.application_credential_name.clone());
+        fields
+            .application_credential_secret
+            .set_value(credentials.application_credential_secret.clone());
+        fields.secret_storage.index = SecretStorage::ALL
+            .iter()
+            .position(|s| *s == config.secret_storage)
+            .unwrap_or_default();
+        fields
+            .identity_url
+            .set_value(credentials.identity_url.clone());
+        fields.region.set_value(config.region.clone());
+        fields.columns = config.columns.clone();
+        fields
+            .refresh_interval
+            .set_value(config.refresh_interval.to_string());
+        fields
+            .max_microversion
+            .set_value(config.max_microversion.clone());
+        fields.ca_bundle.set_value(config.ca_bundle.clone());
+        fields.insecure.index = usize::from(config.insecure);
+        fields.max_retries.set_value(config.max_retries.to_string());
+        fields.retry_delay.set_value(config.retry_delay.to_string());
+        fields.interface.index = Interface::ALL
+            .iter()
+            .position(|i| *i == config.interface)
+            .unwrap_or_default();
+        fields
+    }
+}
+
+impl Fields {
+    fn auth_method(&self) -> AuthMethod {
+        AuthMethod::ALL[self.auth_method.index]
+    }
+
+    fn scope(&self) -> Scope {
+        Scope::ALL[self.scope.index]
+    }
+
+    fn secret_storage(&self) -> SecretStorage {
+        SecretStorage::ALL[self.secret_storage.index]
+    }
+
+    // Shown next to a field whose value is allowed but risky
+    fn warning(&self, focus: Focus) -> Option<&'static str> {
+        match focus {
+            Focus::SecretStorage if self.secret_storage() == SecretStorage::Plain => {
+                Some("saved unencrypted in profiles.json")
+            }
+            _ => None,
+        }
+    }
+
+    // Fields shown for the selected auth method and scope, in focus order
+    fn visible(&self) -> Vec<Focus> {
+        let user = [
+            Focus::UserId,
+            Focus::Username,
+            Focus::UserDomainName,
+            Focus::UserDomainId,
+        ];
+        let mut visible = vec![Focus::Name, Focus::AuthMethod];
+        match self.auth_method() {
+            AuthMethod::Password => {
+                visible.extend(user);
+                visible.extend([Focus::Password, Focus::Scope]);
+                match self.scope() {
+                    Scope::Project => visible.extend([
+                        Focus::TenantId,
+                        Focus::ProjectName,
+                        Focus::ProjectDomainName,
+                        Focus::ProjectDomainId,
+                    ]),
+                    Scope::Domain => visible.extend([Focus::DomainId, Focus::DomainName]),
+                    Scope::Unscoped => {}
+                }
+            }
+            AuthMethod::ApplicationCredential => {
+                visible.extend([
+                    Focus::ApplicationCredentialId,
+                    Focus::ApplicationCredentialName,
+                ]);
+                visible.extend(user);
+                visible.push(Focus::ApplicationCredentialSecret);
+            }
+        }
+        visible.push(Focus::SecretStorage);
+        if self.secret_storage() == SecretStorage::Encrypted {
+            visible.push(Focus::Passphrase);
+        }
+        visible.extend([
+            Focus::IdentityUrl,
+            Focus::Region,
+            Focus::Interface,
+            Focus::RefreshInterval,
+            Focus::MaxMicroversion,
+            Focus::CaBundle,
+            Focus::Insecure,
+            Focus::MaxRetries,
+            Focus::RetryDelay,
+        ]);
+        visible
+    }
+
+    fn field(&self, focus: Focus) -> &dyn Field {
+        match focus {
+            Focus::Name => &self.name,
+            Focus::AuthMethod => &self.auth_method,
+            Focus::UserId => &self.userid,
+            Focus::Username => &self.username,
+            Focus::UserDomainName => &self.user_domain_name,
+            Focus::UserDomainId => &self.user_domain_id,
+            Focus::Password => &self.password,
+            Focus::Scope => &self.scope,
+            Focus::TenantId => &self.tenantid,
+            Focus::ProjectName => &self.project_name,
+            Focus::ProjectDomainName => &self.project_domain_name,
+            Focus::ProjectDomainId => &self.project_domain_id,
+            Focus::DomainId => &self.domain_id,
+            Focus::DomainName => &self.domain_name,
+            Focus::ApplicationCredentialId => &self.application_credential_id,
+            Focus::ApplicationCredentialName => &self.application_credential_name,
+            Focus::ApplicationCredentialSecret => &self.application_credential_secret,
+            Focus::SecretStorage => &self.secret_storage,
+            Focus::Passphrase => &self.passphrase,
+            Focus::IdentityUrl => &self.identity_url,
+            Focus::Region => &self.region,
+            Focus::Interface => &self.interface,
+            Focus::RefreshInterval => &self.refresh_interval,
+            Focus::MaxMicroversion => &self.max_microversion,
+            Focus::CaBundle => &self.ca_bundle,
+            Focus::Insecure => &self.insecure,
+            Focus::MaxRetries => &self.max_retries,
+            Focus::RetryDelay => &self.retry_delay,
+        }
+    }
+
+    fn field_mut(&mut self, focus: Focus) -> &mut dyn Field {
+        match focus {
+            Focus::Name => &mut self.name,
+            Focus::AuthMethod => &mut self.auth_method,
+            Focus::UserId => &mut self.userid,
+            Focus::Username => &mut self.username,
+            Focus::UserDomainName => &mut self.user_domain_name,
+            Focus::UserDomainId => &mut self.user_domain_id,
+            Focus::Password => &mut self.password,
+            Focus::Scope => &mut self.scope,
+            Focus::TenantId => &mut self.tenantid,
+            Focus::ProjectName => &mut self.project_name,
+            Focus::ProjectDomainName => &mut self.project_domain_name,
+            Focus::ProjectDomainId => &mut self.project_domain_id,
+            Focus::DomainId => &mut self.domain_id,
+            Focus::DomainName => &mut self.domain_name,
+            Focus::ApplicationCredentialId => &mut self.application_credential_id,
+            Focus::ApplicationCredentialName => &mut self.application_credential_name,
+            Focus::ApplicationCredentialSecret => &mut self.application_credential_secret,
+            Focus::SecretStorage => &mut self.secret_storage,
+            Focus::Passphrase => &mut self.passphrase,
+            Focus::IdentityUrl => &mut self.identity_url,
+            Focus::Region => &mut self.region,
+            Focus::Interface => &mut self.interface,
+            Focus::RefreshInterval => &mut self.refresh_interval,
+            Focus::MaxMicroversion => &mut self.max_microversion,
+            Focus::CaBundle => &mut self.ca_bundle,
+            Focus::Insecure => &mut self.insecure,
+            Focus::MaxRetries => &mut self.max_retries,
+            Focus::RetryDelay => &mut self.retry_delay,
+        }
+    }
+
+    // Override a value with one from clouds.yaml or the environment
+    fn apply(&mut self, setting: &Setting) {
+        let focus = Focus::from(setting.key);
+        self.field_mut(focus).set_value(setting.value.clone());
+        self.sources.insert(focus, setting.source.clone());
+    }
+
+    fn profile(&self) -> Profile {
+        Profile {
+            name: self.name.value().trim().to_string(),
+            credentials: self.credentials(),
+            region: self.region.value().trim().to_string(),
+            interface: Interface::ALL[self.interface.index],
+            secret_storage: self.secret_storage(),
+            columns: self.columns.clone(),
+            refresh_interval: self
+                .refresh_interval
+                .value()
+                .trim()
+                .parse()
+                .unwrap_or(DEFAULT_REFRESH_INTERVAL),
+            max_microversion: self.max_microversion.value().trim().to_string(),
+            ca_bundle: self.ca_bundle.value().trim().to_string(),
+            insecure: self.insecure.index == 1,
+            max_retries: self
+                .max_retries
+                .value()
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| RetryPolicy::default().max_retries),
+            retry_delay: self
+                .retry_delay
+                .value()
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| RetryPolicy::default().base_delay.as_millis() as u64),
+        }
+    }
+
+    fn credentials(&self) -> Credentials {
+        Credentials {
+            method: self.auth_method(),
+            userid: self.userid.value().to_string(),
+            username: self.username.value().to_string(),
+            user_domain_id: self.user_domain_id.value().to_string(),
+            user_domain_name: self.user_domain_name.value().to_string(),
+            password: self.password.value().to_string(),
+            scope: self.scope(),
+            tenantid: self.tenantid.value().to_string(),
+            project_name: self.project_name.value().to_string(),
+            project_domain_id: self.project_domain_id.value().to_string(),
+            project_domain_name: self.project_domain_name.value().to_string(),
+            domain_id: self.domain_id.value().to_string(),
+            domain_name: self.domain_name.value().to_string(),
+            application_credential_id: self.application_credential_id.value().to_string(),
+            application_credential_name: self.application_credential_name.value().to_string(),
+            application_credential_secret: self.application_credential_secret.value().to_string(),
+            identity_url: self.identity_url.value().to_string(),
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+impl Fields {
+    // Problems with the visible fields, keyed by the field to show them next to.
+    // Missing values are only reported with `required`, so a new form starts clean
+    fn errors(&self, required: bool) -> HashMap<Focus, String> {
+        let mut errors = HashMap::new();
+        let visible = self.visible();
+        for focus in &visible {
+            let value = self.field(*focus).value();
+            if value.is_empty() {
+                continue;
+            }
+            let error = match focus {
+                Focus::IdentityUrl => check_url(value),
+                Focus::RefreshInterval => check_seconds(value),
+                Focus::MaxMicroversion => value
+                    .parse::<Microversion>()
+                    .err()
+                    .map(|_| "must look like 2.79".to_string()),
+                Focus::CaBundle => check_file(value),
+                Focus::MaxRetries | Focus::RetryDelay => check_count(value),
+                Focus::UserId | Focus::TenantId | Focus::ApplicationCredentialId => check_id(value),
+                // Keystone's built-in domain has the ID "default"
+                Focus::UserDomainId | Focus::ProjectDomainId | Focus::DomainId
+                    if value != "default" =>
+                {
+                    check_id(value)
+                }
+                _ => None,
+            };
+            if let Some(error) = error {
+                errors.insert(*focus, error);
+            }
+        }
+        if !required {
+            return errors;
+        }
+
+        let mut require = |focus: Focus, message: &str| {
+            errors.entry(focus).or_insert_with(|| message.to_string());
+        };
+        let filled = |focus: Focus| !self.field(focus).value().is_empty();
+        let either = |id: Focus, name: Focus| filled(id) || filled(name);
+
+        if !filled(Focus::Name) {
+            require(Focus::Name, "required");
+        }
+        let needs_user = match self.auth_method() {
+            AuthMethod::Password => {
+                if !filled(Focus::Password) {
+                    require(Focus::Password, "required");
+                }
+                match self.scope() {
+                    Scope::Project => {
+                        if !either(Focus::TenantId, Focus::ProjectName) {
+                            require(Focus::TenantId, "enter a project ID or name");
+                        } else if !filled(Focus::TenantId)
+                            && !either(Focus::ProjectDomainId, Focus::ProjectDomainName)
+                        {
+                            require(Focus::ProjectDomainName, "required with a project name");
+                        }
+                    }
+                    Scope::Domain => {
+                        if !either(Focus::DomainId, Focus::DomainName) {
+                            require(Focus::DomainId, "enter a domain ID or name");
+                        }
+                    }
+                    Scope::Unscoped => {}
+                }
+                true
+            }
+            AuthMethod::ApplicationCredential => {
+                if !filled(Focus::ApplicationCredentialSecret) {
+                    require(Focus::ApplicationCredentialSecret, "required");
+                }
+                if !either(
+                    Focus::ApplicationCredentialId,
+                    Focus::ApplicationCredentialName,
+                ) {
+                    require(Focus::ApplicationCredentialId, "enter an ID or name");
+                }
+                // a credential name is only unique per user
+                !filled(Focus::ApplicationCredentialId)
+            }
+        };
+        if needs_user {
+            if !either(Focus::UserId, Focus::Username) {
+                require(Focus::UserId, "enter a user ID or name");
+            } else if !filled(Focus::UserId) && !either(Focus::UserDomainId, Focus::UserDomainName)
+            {
+                require(Focus::UserDomainName, "required with a user name");
+            }
+        }
+        if visible.contains(&Focus::Passphrase) && !filled(Focus::Passphrase) {
+            require(Focus::Passphrase, "required to encrypt secrets");
+        }
+        if !filled(Focus::IdentityUrl) {
+            require(Focus::IdentityUrl, "required");
+        }
+        errors
+    }
+}
+
+// Form for creating or editing one profile
+#[derive(Clone, Default)]
 pub struct Config {
-    #[serde(skip, default)]
     focus: Focus,
-
-    #[serde(skip, default)]
     pub message: String,
-
-    #[serde(skip, default)]
+    // failed connection test or login, shown over the form until dismissed
+    pub error: Option<Report>,
     fields: Fields,
-
-    pub userid: String,
-    pub password: String,
-    pub tenantid: String,
-    pub identity_url: String,
+    // name the profile is stored under, None until it has been saved
+    original_name: Option<String>,
+    // report empty required fields once the user tried to connect
+    submitted: bool,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            focus: Focus::UserId,
-            message: String::new(),
-            fields: Fields::default(),
-            userid: String::new(),
-            password: String::new(),
-            tenantid: String::new(),
-            identity_url: String::new(),
+impl From<&Profile> for Config {
+    fn from(profile: &Profile) -> Self {
+        let mut config = Config::new_profile(profile);
+        for focus in Focus::ALL {
+            if !config.fields.field(focus).is_empty() {
+                config.fields.sources.insert(focus, Source::Profile);
+            }
         }
+        config.original_name = Some(profile.name.clone());
+        // secrets that are not stored in profiles.json have to be typed in
+        config.focus = match (profile.secret_storage, profile.credentials.method) {
+            (SecretStorage::Plain, _) => Focus::default(),
+            (SecretStorage::Encrypted, _) => Focus::Passphrase,
+            (SecretStorage::Prompt, AuthMethod::Password) => Focus::Password,
+            (SecretStorage::Prompt, AuthMethod::ApplicationCredential) => {
+                Focus::ApplicationCredentialSecret
+            }
+        };
+        config
     }
 }
 
 impl Config {
+    // A form for a profile that has not been saved yet
+    pub fn new_profile(profile: &Profile) -> Self {
+        Self {
+            fields: Fields::from(profile),
+            ..Default::default()
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
-        validate(self)
+        self.fields.errors(true).is_empty()
+    }
+
+    pub fn is_stored(&self) -> bool {
+        self.original_name.is_some()
+    }
+
+    pub fn name(&self) -> String {
+        self.fields.name.value().trim().to_string()
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.fields.profile()
     }
 
     pub fn render(&self, frame: &mut Frame) {
-        let layout = Layout::vertical(Constraint::from_lengths([1, 1, 1, 1, 1]));
-        let [
-            message_area,
-            userid_area,
-            password_area,
-            tenantid_area,
-            identity_url_area,
-        ] = frame.area().layout(&layout);
+        let visible = self.fields.visible();
+        let layout = Layout::vertical(
+            std::iter::repeat_n(Constraint::Length(1), visible.len() + 1)
+                .chain([Constraint::Fill(1)]),
+        );
+        let areas = frame.area().layout_vec(&layout);
+        let errors = self.fields.errors(self.submitted);
 
         let message = Paragraph::new(self.message.clone());
-        frame.render_widget(&message, message_area);
-        frame.render_widget(&self.fields.userid, userid_area);
-        frame.render_widget(&self.fields.password, password_area);
-        frame.render_widget(&self.fields.tenantid, tenantid_area);
-        frame.render_widget(&self.fields.identity_url, identity_url_area);
-
-        let cursor_position = match self.focus {
-            Focus::UserId => userid_area.offset(self.fields.userid.cursor_offset()),
-            Focus::Password => password_area.offset(self.fields.password.cursor_offset()),
-            Focus::TenantId => tenantid_area.offset(self.fields.tenantid.cursor_offset()),
-            Focus::IdentityUrl => {
-                identity_url_area.offset(self.fields.identity_url.cursor_offset())
+        frame.render_widget(&message, areas[0]);
+        for (focus, area) in visible.iter().zip(&areas[1..]) {
+            let field = self.fields.field(*focus);
+            field.render_line(*area, frame.buffer_mut());
+            let mut notes = Vec::new();
+            if let Some(error) = errors.get(focus) {
+                notes.push(format!("{} ", error).red());
+            } else if let Some(warning) = self.fields.warning(*focus) {
+                notes.push(format!("{} ", warning).yellow());
             }
-        };
-        frame.set_cursor_position(cursor_position);
+            if let Some(source) = self.fields.sources.get(focus) {
+                notes.push(format!("[{}]", source).dim());
+            }
+            frame.render_widget(Line::from(notes).right_aligned(), *area);
+            if *focus == self.focus {
+                frame.set_cursor_position(area.offset(field.cursor_offset(area.width)));
+            }
+        }
+        let help =
+            Line::from("Tab/Shift+Tab move, Enter connect, Ctrl+S save as profile, Ctrl+T test connection, Esc back").dim();
+        frame.render_widget(help, areas[visible.len() + 1]);
+        if let Some(error) = &self.error {
+            frame.render_widget(error, frame.area());
+        }
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.fields.field_mut(self.focus).paste(text);
+        self.fields.sources.remove(&self.focus);
     }
 
     pub fn handle_events(&mut self, event: Option<KeyEvent>) -> state::AppState {
         if let Some(key) = event {
             match key.code {
+                KeyCode::Esc if self.error.is_some() => {
+                    self.error = None;
+                    return state::AppState::Loading;
+                }
                 KeyCode::Esc => {
-                    return state::AppState::Quit;
+                    return state::AppState::Profiles;
                 }
                 KeyCode::Tab => {
-                    self.focus = self.focus.next();
+                    self.move_focus(1);
+                    return state::AppState::Loading;
+                }
+                KeyCode::BackTab => {
+                    self.move_focus(-1);
                     return state::AppState::Loading;
                 }
                 KeyCode::Enter => {
-                    if self.is_valid() {
-                        if let Err(e) = self.save() {
-                            self.message = format!("Error saving config: {}", e);
-                            return state::AppState::Loading;
-                        }
-                        return state::AppState::IssueToken {
-                            userid: self.fields.userid.value.clone(),
-                            password: self.fields.password.value.clone(),
-                            tenantid: self.fields.tenantid.value.clone(),
-                            identity_url: self.fields.identity_url.value.clone(),
+                    if let Err(e) = self.unlock_secrets() {
+                        self.message = format!("Error unlocking secrets: {}", e);
+                        return state::AppState::Loading;
+                    }
+                    if !self.check_fields() {
+                        return state::AppState::Loading;
+                    }
+                    // the environment entry and new profiles are only saved on Ctrl+S
+                    if self.is_stored()
+                        && let Err(e) = self.save()
+                    {
+                        self.message = format!("Error saving config: {}", e);
+                        return state::AppState::Loading;
+                    }
+                    return state::AppState::IssueToken(Box::new(self.profile()));
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Err(e) = self.unlock_secrets() {
+                        self.message = format!("Error unlocking secrets: {}", e);
+                        return state::AppState::Loading;
+                    }
+                    if self.check_fields() {
+                        self.message = match self.save() {
+                            Ok(()) => format!("Saved profile {}", self.name()),
+                            Err(e) => format!("Error saving config: {}", e),
                         };
                     }
-
-                    self.message = "Please fill in all fields.".to_string();
                     return state::AppState::Loading;
                 }
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let url = self.fields.identity_url.value().trim().to_string();
+                    if let Some(error) = check_url(&url) {
+                        self.message = format!("Identity URL {}", error);
+                        return state::AppState::Loading;
+                    }
+                    self.message = format!("Testing {} ...", url);
+                    return state::AppState::TestConnection(url);
+                }
                 _ => {
-                    match self.focus {
-                        Focus::UserId => self.fields.userid.on_key_press(key),
-                        Focus::Password => self.fields.password.on_key_press(key),
-                        Focus::TenantId => self.fields.tenantid.on_key_press(key),
-                        Focus::IdentityUrl => self.fields.identity_url.on_key_press(key),
-                    };
+                    self.fields.field_mut(self.focus).on_key_press(key);
+                    // the value is now whatever the user typed
+                    self.fields.sources.remove(&self.focus);
                     return state::AppState::Loading;
                 }
             }
         }
 
-        return state::AppState::Loading;
+        state::AppState::Loading
     }
 
-    fn save(&mut self) -> Result<()> {
-        let config_path = match dirs::config_dir() {
-            Some(path) => path.join("ratatui-sample/config.json"),
-            None => return Ok(()),
-        };
-        if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)?;
+    // Show every missing or malformed field, false when there are any
+    fn check_fields(&mut self) -> bool {
+        self.submitted = true;
+        let errors = self.fields.errors(true);
+        if errors.is_empty() {
+            return true;
         }
 
-        self.userid = self.fields.userid.value.clone();
-        self.password = self.fields.password.value.clone();
-        self.tenantid = self.fields.tenantid.value.clone();
-        self.identity_url = self.fields.identity_url.value.clone();
+        // jump to the first field that needs fixing
+        if let Some(focus) = self
+            .fields
+            .visible()
+            .into_iter()
+            .find(|f| errors.contains_key(f))
+        {
+            self.focus = focus;
+        }
+        self.message = "Please fix the highlighted fields.".to_string();
+        false
+    }
 
-        let config_str = serde_json::to_string(self)?;
-        std::fs::write(config_path, config_str)?;
+    fn move_focus(&mut self, step: isize) {
+        let visible = self.fields.visible();
+        let current = visible.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(visible.len() as isize);
+        self.focus = visible[next as usize];
+    }
+
+    // Fill in empty secrets from secrets.enc when a passphrase was given
+    fn unlock_secrets(&mut self) -> Result<()> {
+        let passphrase = self.fields.passphrase.value();
+        if self.fields.secret_storage() != SecretStorage::Encrypted || passphrase.is_empty() {
+            return Ok(());
+        }
+
+        let store = SecretStore::unlock(passphrase)?;
+        let name = self.original_name.clone().unwrap_or_else(|| self.name());
+        if let Some(secrets) = store.profiles.get(&name) {
+            if self.fields.password.is_empty() {
+                self.fields.password.set_value(secrets.password.clone());
+            }
+            if self.fields.application_credential_secret.is_empty() {
+                self.fields
+                    .application_credential_secret
+                    .set_value(secrets.application_credential_secret.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        let profile = self.profile();
+        let name = profile.name.clone();
+
+        if profile.secret_storage == SecretStorage::Encrypted {
+            let passphrase = self.fields.passphrase.value();
+            if passphrase.is_empty() {
+                return Err(eyre!("A passphrase is required to encrypt the secrets"));
+            }
+            let mut secrets = SecretStore::unlock(passphrase)?;
+            if let Some(original) = &self.original_name {
+                secrets.profiles.remove(original);
+            }
+            secrets.profiles.insert(
+                name.clone(),
+                Secrets {
+                    password: profile.credentials.password.clone(),
+                    application_credential_secret: profile
+                        .credentials
+                        .application_credential_secret
+                        .clone(),
+                },
+            );
+            secrets.save(passphrase)?;
+        }
+
+        let mut store = Store::load()?;
+        store.upsert(self.original_name.as_deref(), profile)?;
+        store.save()?;
+        self.original_name = Some(name);
 
         Ok(())
     }
 }
 
-#[derive(Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 enum Focus {
     #[default]
+    Name,
+    AuthMethod,
     UserId,
+    Username,
+    UserDomainName,
+    UserDomainId,
     Password,
+    Scope,
     TenantId,
+    ProjectName,
+    ProjectDomainName,
+    ProjectDomainId,
+    DomainId,
+    DomainName,
+    ApplicationCredentialId,
+    ApplicationCredentialName,
+    ApplicationCredentialSecret,
+    SecretStorage,
+    Passphrase,
     IdentityUrl,
+    Region,
+    Interface,
+    RefreshInterval,
+    MaxMicroversion,
+    CaBundle,
+    Insecure,
+    MaxRetries,
+    RetryDelay,
 }
 
 impl Focus {
-    const fn next(&self) -> Self {
-        match self {
-            Self::UserId => Self::Password,
-            Self::Password => Self::TenantId,
-            Self::TenantId => Self::IdentityUrl,
-            Self::IdentityUrl => Self::UserId,
+    const ALL: [Focus; 28] = [
+        Focus::Name,
+        Focus::AuthMethod,
+        Focus::UserId,
+        Focus::Username,
+        Focus::UserDomainName,
+        Focus::UserDomainId,
+        Focus::Password,
+        Focus::Scope,
+        Focus::TenantId,
+        Focus::ProjectName,
+        Focus::ProjectDomainName,
+        Focus::ProjectDomainId,
+        Focus::DomainId,
+        Focus::DomainName,
+        Focus::ApplicationCredentialId,
+        Focus::ApplicationCredentialName,
+        Focus::ApplicationCredentialSecret,
+        Focus::SecretStorage,
+        Focus::Passphrase,
+        Focus::IdentityUrl,
+        Focus::Region,
+        Focus::Interface,
+        Focus::RefreshInterval,
+        Focus::MaxMicroversion,
+        Focus::CaBundle,
+        Focus::Insecure,
+        Focus::MaxRetries,
+        Focus::RetryDelay,
+    ];
+}
+
+impl From<Key> for Focus {
+    fn from(key: Key) -> Self {
+        match key {
+            Key::AuthMethod => Focus::AuthMethod,
+            Key::UserId => Focus::UserId,
+            Key::Username => Focus::Username,
+            Key::UserDomainName => Focus::UserDomainName,
+            Key::UserDomainId => Focus::UserDomainId,
+            Key::Password => Focus::Password,
+            Key::Scope => Focus::Scope,
+            Key::TenantId => Focus::TenantId,
+            Key::ProjectName => Focus::ProjectName,
+            Key::ProjectDomainName => Focus::ProjectDomainName,
+            Key::ProjectDomainId => Focus::ProjectDomainId,
+            Key::DomainId => Focus::DomainId,
+            Key::DomainName => Focus::DomainName,
+            Key::ApplicationCredentialId => Focus::ApplicationCredentialId,
+            Key::ApplicationCredentialName => Focus::ApplicationCredentialName,
+            Key::ApplicationCredentialSecret => Focus::ApplicationCredentialSecret,
+            Key::IdentityUrl => Focus::IdentityUrl,
+            Key::Region => Focus::Region,
+            Key::Interface => Focus::Interface,
+            Key::CaBundle => Focus::CaBundle,
+            Key::Insecure => Focus::Insecure,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct StringField {
-    #[serde(skip)]
-    label: String,
-    pub value: String,
+fn refresh_interval_field() -> StringField {
+    let mut field = StringField::new("Refresh Interval (s)".to_string());
+    field.set_value(DEFAULT_REFRESH_INTERVAL.to_string());
+    field
 }
 
-impl StringField {
-    fn new(label: String) -> Self {
-        Self {
-            label,
-            value: String::new(),
-        }
+fn max_microversion_field() -> StringField {
+    let mut field = StringField::new("Max Compute Microversion".to_string());
+    field.set_value(Microversion::DEFAULT_MAX.to_string());
+    field
+}
+
+fn max_retries_field() -> StringField {
+    let mut field = StringField::new("Max Retries".to_string());
+    field.set_value(RetryPolicy::default().max_retries.to_string());
+    field
+}
+
+fn retry_delay_field() -> StringField {
+    let mut field = StringField::new("Retry Delay (ms)".to_string());
+    field.set_value(RetryPolicy::default().base_delay.as_millis().to_string());
+    field
+}
+
+fn auth_method_options() -> Vec<String> {
+    AuthMethod::ALL
+        .iter()
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+fn scope_options() -> Vec<String> {
+    Scope::ALL.iter().map(|s| s.as_str().to_string()).collect()
+}
+
+fn secret_storage_options() -> Vec<String> {
+    SecretStorage::ALL
+        .iter()
+        .map(|s| s.as_str().to_string())
+        .collect()
+}
+
+fn interface_options() -> Vec<String> {
+    Interface::ALL
+        .iter()
+        .map(|i| i.as_str().to_string())
+        .collect()
+}
+
+fn yes_no_options() -> Vec<String> {
+    vec!["no".to_string(), "yes".to_string()]
+}
+
+// Profile built from clouds.yaml, secure.yaml and OS_* variables, if any are set,
+// and the errors of the files that could not be read
+pub fn from_environment() -> (Option<Config>, Vec<String>) {
+    let (settings, errors) = clouds::load();
+    if settings.is_empty() {
+        return (None, errors);
     }
 
-    fn on_key_press(&mut self, event: KeyEvent) {
-        match event.code {
-            KeyCode::Char(c) => self.value.push(c),
-            KeyCode::Backspace => {
-                self.value.pop();
-            }
-            _ => {}
-        }
+    let mut config = Config::default();
+    config
+        .fields
+        .name
+        .set_value(std::env::var("OS_CLOUD").unwrap_or_else(|_| "environment".to_string()));
+    for setting in &settings {
+        config.fields.apply(setting);
     }
+    (Some(config), errors)
+}
 
-    fn cursor_offset(&self) -> Offset {
-        let x = (self.label.len() + self.value.len() + 2) as i32;
-        Offset::new(x, 0)
+// Well-formed http(s) URL with a host
+fn check_url(value: &str) -> Option<String> {
+    match Url::parse(value.trim()) {
+        Ok(url) if !matches!(url.scheme(), "http" | "https") => {
+            Some("must be an http or https URL".to_string())
+        }
+        Ok(url) if url.host_str().is_none_or(str::is_empty) => Some("URL has no host".to_string()),
+        Ok(_) => None,
+        Err(e) => Some(format!("invalid URL: {}", e)),
     }
 }
 
-impl Widget for &StringField {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let layout = Layout::horizontal([
-            Constraint::Length(self.label.len() as u16 + 2),
-            Constraint::Fill(1),
-        ]);
-        let [label_area, value_area] = area.layout(&layout);
-        let label = Line::from_iter([self.label.clone(), ": ".to_string()]).bold();
-        label.render(label_area, buf);
-        self.value.clone().render(value_area, buf);
+// Keystone IDs are 32 hex digits, some backends use dashed UUIDs or 64 hex digits
+fn check_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = |s: &str| s.chars().all(|c| c.is_ascii_hexdigit());
+    let dashed = value.split('-').map(str::len).collect::<Vec<_>>() == [8, 4, 4, 4, 12]
+        && hex(&value.replace('-', ""));
+    if dashed || (matches!(value.len(), 32 | 64) && hex(value)) {
+        None
+    } else {
+        Some("must be a hex ID or UUID".to_string())
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct PasswordField {
-    #[serde(skip)]
-    label: String,
-    #[serde(skip)]
-    display_value: String,
-    value: String,
+fn check_seconds(value: &str) -> Option<String> {
+    match value.trim().parse::<u64>() {
+        Ok(_) => None,
+        Err(_) => Some("must be a number of seconds, 0 to turn off".to_string()),
+    }
 }
 
-impl PasswordField {
-    fn new(label: String) -> Self {
-        Self {
-            label,
-            display_value: String::new(),
-            value: String::new(),
-        }
+fn check_count(value: &str) -> Option<String> {
+    match value.trim().parse::<u32>() {
+        Ok(_) => None,
+        Err(_) => Some("must be a whole number".to_string()),
     }
+}
 
-    fn on_key_press(&mut self, event: KeyEvent) {
-        match event.code {
-            KeyCode::Char(c) => {
-                self.value.push(c);
-                self.display_value.push('*');
-            }
-            KeyCode::Backspace => {
-                self.value.pop();
-                self.display_value.pop();
-            }
-            _ => {}
-        }
+fn check_file(value: &str) -> Option<String> {
+    (!std::path::Path::new(value.trim()).is_file()).then(|| "file not found".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_url() {
+        assert_eq!(check_url("http://keystone:5000/v3"), None);
+        assert_eq!(check_url("https://cloud.example.com/identity"), None);
+        assert!(check_url("keystone:5000").is_some());
+        assert!(check_url("ftp://keystone").is_some());
+        assert!(check_url("http://").is_some());
     }
 
-    fn cursor_offset(&self) -> Offset {
-        let x = (self.label.len() + self.value.len() + 2) as i32;
-        Offset::new(x, 0)
+    #[test]
+    fn test_check_id() {
+        assert_eq!(check_id("ee4dfb6e5540447cb3741905149d9b6e"), None);
+        assert_eq!(check_id("978a4c30-92ef-45c5-bf65-d5624f52a295"), None);
+        assert!(check_id("ee4dfb6e").is_some());
+        assert!(check_id("my-project").is_some());
     }
-}
 
-impl Widget for &PasswordField {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let layout = Layout::horizontal([
-            Constraint::Length(self.label.len() as u16 + 2),
-            Constraint::Fill(1),
-        ]);
-        let [label_area, value_area] = area.layout(&layout);
-        let label = Line::from_iter([self.label.clone(), ": ".to_string()]).bold();
-        label.render(label_area, buf);
-        self.display_value.clone().render(value_area, buf);
+    #[test]
+    fn test_field_errors() {
+        let mut profile = Profile {
+            name: "dev".to_string(),
+            ..Default::default()
+        };
+        profile.credentials.username = "alice".to_string();
+        profile.credentials.password = "secret".to_string();
+        profile.credentials.tenantid = "not-an-id".to_string();
+        profile.credentials.identity_url = "localhost:5000".to_string();
+        let fields = Fields::from(&profile);
+
+        // malformed values are reported while typing
+        let errors = fields.errors(false);
+        assert!(errors.contains_key(&Focus::TenantId));
+        assert!(errors.contains_key(&Focus::IdentityUrl));
+        assert!(!errors.contains_key(&Focus::UserDomainName));
+
+        // missing values only once the user tries to connect
+        let errors = fields.errors(true);
+        assert_eq!(
+            errors.get(&Focus::UserDomainName).map(String::as_str),
+            Some("required with a user name")
+        );
+        assert!(!errors.contains_key(&Focus::Password));
+
+        profile.credentials.user_domain_id = "default".to_string();
+        profile.credentials.tenantid = "a6944d763bf64ee6a275f1263fae0352".to_string();
+        profile.credentials.identity_url = "http://localhost:5000/v3".to_string();
+        assert!(Fields::from(&profile).errors(true).is_empty());
     }
-}
 
-pub fn load() -> Config {
-    let config_path = match dirs::config_dir() {
-        Some(path) => path.join("ratatui-sample/config.json"),
-        None => return Config::default(),
-    };
-    if !config_path.exists() {
-        return Config::default();
-    }
-
-    let config_str = match std::fs::read_to_string(config_path) {
-        Ok(content) => content,
-        Err(_) => return Config::default(),
-    };
-    let mut config: Config = match serde_json::from_str(&config_str) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to parse config: {}", e.to_string().red());
-            return Config::default();
-        }
-    };
-    config.fields = Fields::from(&config);
+    #[test]
+    fn test_connect_does_not_save_unstored_profile() {
+        let mut profile = Profile {
+            name: "environment".to_string(),
+            ..Default::default()
+        };
+        profile.credentials.username = "alice".to_string();
+        profile.credentials.user_domain_id = "default".to_string();
+        profile.credentials.password = "secret".to_string();
+        profile.credentials.tenantid = "a6944d763bf64ee6a275f1263fae0352".to_string();
+        profile.credentials.identity_url = "http://localhost:5000/v3".to_string();
+        let mut config = Config::new_profile(&profile);
 
-    if validate(&config) {
-        config
-    } else {
-        eprintln!("Invalid config: Missing required fields");
-        Config::default()
+        // connecting again must not run into the copy saved the first time
+        let enter = KeyEvent::from(KeyCode::Enter);
+        for _ in 0..2 {
+            let next = config.handle_events(Some(enter));
+            assert!(matches!(next, state::AppState::IssueToken(_)));
+            assert!(!config.is_stored());
+        }
     }
-}
 
-fn validate(config: &Config) -> bool {
-    !config.fields.userid.value.is_empty()
-        && !config.fields.password.value.is_empty()
-        && !config.fields.tenantid.value.is_empty()
-        && !config.fields.identity_url.value.is_empty()
+    #[test]
+    fn test_plain_secret_warning() {
+        let mut profile = Profile::default();
+        assert!(
+            Fields::from(&profile)
+                .warning(Focus::SecretStorage)
+                .is_some()
+        );
+        profile.secret_storage = SecretStorage::Prompt;
+        assert_eq!(Fields::from(&profile).warning(Focus::SecretStorage), None);
+    }
 }
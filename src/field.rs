@@ -0,0 +1,225 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Offset, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::Widget;
+use ratatui_core::style::Stylize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::input::TextInput;
+
+// A single line of a form
+pub trait Field {
+    fn set_value(&mut self, value: String);
+    fn value(&self) -> &str;
+    fn is_empty(&self) -> bool;
+    fn on_key_press(&mut self, event: KeyEvent);
+    // bracketed paste, ignored by fields that don't take text
+    fn paste(&mut self, _text: &str) {}
+    fn cursor_offset(&self, width: u16) -> Offset;
+    fn render_line(&self, area: Rect, buf: &mut Buffer);
+}
+
+#[derive(Debug, Clone)]
+pub struct StringField {
+    label: String,
+    input: TextInput,
+}
+
+impl StringField {
+    pub fn new(label: String) -> Self {
+        Self {
+            label,
+            input: TextInput::new(),
+        }
+    }
+}
+
+impl Field for StringField {
+    fn set_value(&mut self, value: String) {
+        self.input.set_value(value);
+    }
+
+    fn value(&self) -> &str {
+        self.input.value()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn on_key_press(&mut self, event: KeyEvent) {
+        self.input.on_key_press(event);
+    }
+
+    fn paste(&mut self, text: &str) {
+        self.input.paste(text);
+    }
+
+    fn cursor_offset(&self, width: u16) -> Offset {
+        input_cursor_offset(&self.label, &self.input, width)
+    }
+
+    fn render_line(&self, area: Rect, buf: &mut Buffer) {
+        self.render(area, buf);
+    }
+}
+
+impl Widget for &StringField {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        render_input(&self.label, &self.input, area, buf);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordField {
+    label: String,
+    input: TextInput,
+}
+
+impl PasswordField {
+    pub fn new(label: String) -> Self {
+        Self {
+            label,
+            input: TextInput::masked(),
+        }
+    }
+}
+
+impl Field for PasswordField {
+    fn set_value(&mut self, value: String) {
+        self.input.set_value(value);
+    }
+
+    fn value(&self) -> &str {
+        self.input.value()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn on_key_press(&mut self, event: KeyEvent) {
+        self.input.on_key_press(event);
+    }
+
+    fn paste(&mut self, text: &str) {
+        self.input.paste(text);
+    }
+
+    fn cursor_offset(&self, width: u16) -> Offset {
+        input_cursor_offset(&self.label, &self.input, width)
+    }
+
+    fn render_line(&self, area: Rect, buf: &mut Buffer) {
+        self.render(area, buf);
+    }
+}
+
+impl Widget for &PasswordField {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        render_input(&self.label, &self.input, area, buf);
+    }
+}
+
+pub fn label_width(label: &str) -> u16 {
+    label.width() as u16 + 2
+}
+
+fn input_cursor_offset(label: &str, input: &TextInput, width: u16) -> Offset {
+    let label_width = label_width(label);
+    let x = label_width + input.cursor_x(width.saturating_sub(label_width));
+    Offset::new(x as i32, 0)
+}
+
+fn render_input(label: &str, input: &TextInput, area: Rect, buf: &mut Buffer) {
+    let layout = Layout::horizontal([Constraint::Length(label_width(label)), Constraint::Fill(1)]);
+    let [label_area, value_area] = area.layout(&layout);
+    let label = Line::from_iter([label.to_string(), ": ".to_string()]).bold();
+    label.render(label_area, buf);
+    input.render(value_area, buf);
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectField {
+    label: String,
+    pub options: Vec<String>,
+    pub index: usize,
+}
+
+impl SelectField {
+    pub fn new(label: String, options: Vec<String>) -> Self {
+        Self {
+            label,
+            options,
+            index: 0,
+        }
+    }
+}
+
+impl Field for SelectField {
+    // Select the option with the given name, unknown names are ignored
+    fn set_value(&mut self, value: String) {
+        if let Some(index) = self.options.iter().position(|o| *o == value) {
+            self.index = index;
+        }
+    }
+
+    fn value(&self) -> &str {
+        self.options.get(self.index).map_or("", String::as_str)
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn on_key_press(&mut self, event: KeyEvent) {
+        let len = self.options.len();
+        if len == 0 {
+            return;
+        }
+        match event.code {
+            KeyCode::Right | KeyCode::Char(' ') => self.index = (self.index + 1) % len,
+            KeyCode::Left => self.index = (self.index + len - 1) % len,
+            _ => {}
+        }
+    }
+
+    fn cursor_offset(&self, _width: u16) -> Offset {
+        Offset::new(label_width(&self.label) as i32, 0)
+    }
+
+    fn render_line(&self, area: Rect, buf: &mut Buffer) {
+        self.render(area, buf);
+    }
+}
+
+impl Widget for &SelectField {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::horizontal([
+            Constraint::Length(label_width(&self.label)),
+            Constraint::Fill(1),
+        ]);
+        let [label_area, value_area] = area.layout(&layout);
+        let label = Line::from_iter([self.label.clone(), ": ".to_string()]).bold();
+        label.render(label_area, buf);
+        let value = self.options.get(self.index).cloned().unwrap_or_default();
+        Line::from_iter([value, " (←/→ to change)".to_string()]).render(value_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_field_wide_label() {
+        // "地域" is 6 bytes but 4 columns wide
+        let field = SelectField::new("地域".to_string(), vec!["RegionOne".to_string()]);
+        let area = Rect::new(0, 0, 40, 1);
+        let mut buf = Buffer::empty(area);
+        field.render(area, &mut buf);
+        assert_eq!(buf[(6, 0)].symbol(), "R");
+        assert_eq!(field.cursor_offset(40), Offset::new(6, 0));
+    }
+}
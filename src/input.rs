@@ -0,0 +1,287 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::Widget;
+use unicode_width::UnicodeWidthChar;
+
+// Single line text input with a cursor, shared by every text field in the app
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    value: String,
+    // cursor position in chars, not bytes
+    cursor: usize,
+    // render every char as '*'
+    masked: bool,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn masked() -> Self {
+        Self {
+            masked: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: String) {
+        self.cursor = value.chars().count();
+        self.value = value;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+    }
+
+    // Returns whether the key was used by the input
+    pub fn on_key_press(&mut self, event: KeyEvent) -> bool {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = event.modifiers.contains(KeyModifiers::ALT);
+        match event.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char('u') if ctrl => {
+                self.remove(0, self.cursor);
+            }
+            KeyCode::Char('k') if ctrl => {
+                self.remove(self.cursor, self.len());
+            }
+            KeyCode::Char('w') if ctrl => {
+                self.remove(self.word_start(), self.cursor);
+            }
+            KeyCode::Char('d') if alt => {
+                self.remove(self.cursor, self.word_end());
+            }
+            KeyCode::Char('b') if alt => self.cursor = self.word_start(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace if ctrl || alt => {
+                self.remove(self.word_start(), self.cursor);
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.remove(self.cursor - 1, self.cursor);
+            }
+            KeyCode::Backspace => {}
+            KeyCode::Delete if ctrl || alt => {
+                self.remove(self.cursor, self.word_end());
+            }
+            KeyCode::Delete => {
+                self.remove(self.cursor, (self.cursor + 1).min(self.len()));
+            }
+            KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Bracketed paste, line breaks are dropped since the input is a single line
+    pub fn paste(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.insert(c);
+        }
+    }
+
+    // Display width of the text before the cursor
+    pub fn cursor_width(&self) -> u16 {
+        self.display()
+            .chars()
+            .take(self.cursor)
+            .map(|c| c.width().unwrap_or(0) as u16)
+            .sum()
+    }
+
+    // Cursor column when rendered into an area `width` columns wide
+    pub fn cursor_x(&self, width: u16) -> u16 {
+        self.cursor_width() - self.scroll(width)
+    }
+
+    fn display(&self) -> String {
+        if self.masked {
+            "*".repeat(self.len())
+        } else {
+            self.value.clone()
+        }
+    }
+
+    // Columns hidden on the left so that the cursor stays visible
+    fn scroll(&self, width: u16) -> u16 {
+        let cursor = self.cursor_width();
+        if width == 0 || cursor < width {
+            return 0;
+        }
+        // skip whole chars until the cursor fits
+        let mut skipped = 0;
+        for c in self.display().chars() {
+            if cursor - skipped < width {
+                break;
+            }
+            skipped += c.width().unwrap_or(0) as u16;
+        }
+        skipped
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn byte_index(&self, index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(index)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.value.insert(index, c);
+        self.cursor += 1;
+    }
+
+    fn remove(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.value.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    // Start of the word before the cursor, skipping whitespace first
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    // End of the word after the cursor, skipping whitespace first
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+}
+
+impl Widget for &TextInput {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut skip = self.scroll(area.width);
+        let visible: String = self
+            .display()
+            .chars()
+            .skip_while(|c| {
+                let width = c.width().unwrap_or(0) as u16;
+                if skip == 0 {
+                    return false;
+                }
+                skip = skip.saturating_sub(width);
+                true
+            })
+            .collect();
+        visible.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::CONTROL)
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        for c in text.chars() {
+            input.on_key_press(key(KeyCode::Char(c)));
+        }
+        input
+    }
+
+    #[test]
+    fn test_cursor_editing() {
+        let mut input = typed("helo");
+        input.on_key_press(key(KeyCode::Left));
+        input.on_key_press(key(KeyCode::Char('l')));
+        assert_eq!(input.value(), "hello");
+
+        input.on_key_press(key(KeyCode::Home));
+        input.on_key_press(key(KeyCode::Delete));
+        assert_eq!(input.value(), "ello");
+
+        input.on_key_press(key(KeyCode::End));
+        input.on_key_press(key(KeyCode::Backspace));
+        assert_eq!(input.value(), "ell");
+        assert_eq!(input.cursor_width(), 3);
+    }
+
+    #[test]
+    fn test_word_deletion() {
+        let mut input = typed("openstack server list");
+        input.on_key_press(ctrl(KeyCode::Char('w')));
+        assert_eq!(input.value(), "openstack server ");
+
+        input.on_key_press(key(KeyCode::Home));
+        input.on_key_press(ctrl(KeyCode::Delete));
+        assert_eq!(input.value(), " server ");
+
+        // control chords never insert text
+        assert!(!input.on_key_press(ctrl(KeyCode::Char('x'))));
+        assert_eq!(input.value(), " server ");
+    }
+
+    #[test]
+    fn test_paste_and_wide_chars() {
+        let mut input = typed("名前");
+        assert_eq!(input.cursor_width(), 4);
+
+        input.on_key_press(key(KeyCode::Left));
+        input.paste("a\nb");
+        assert_eq!(input.value(), "名ab前");
+        assert_eq!(input.cursor_width(), 4);
+    }
+
+    #[test]
+    fn test_masked_and_scroll() {
+        let mut input = TextInput::masked();
+        input.set_value("secret".to_string());
+        assert_eq!(input.cursor_width(), 6);
+        // the cursor stays inside a narrow area
+        assert_eq!(input.cursor_x(4), 3);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        (&input).render(buf.area, &mut buf);
+        assert_eq!(buf, Buffer::with_lines(["*** "]));
+    }
+}
@@ -1,18 +1,32 @@
 use color_eyre::Result;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
+use crossterm::execute;
+use std::io::stdout;
 
 pub mod app;
+pub mod clouds;
 pub mod config;
+pub mod field;
+pub mod input;
 pub mod openstack;
+pub mod profile;
+pub mod project;
+pub mod secret;
 pub mod state;
+pub mod totp;
 
-use crate::{
-    app::App,
-};
+use crate::app::App;
 
 async fn tokio_main() -> Result<()> {
     let mut app = App::new();
     let terminal = ratatui::init();
+    // pasted text arrives as one event instead of a burst of key presses,
+    // the mouse scrolls and selects rows in the server table
+    let _ = execute!(stdout(), EnableBracketedPaste, EnableMouseCapture);
     let app_result = app.run(terminal).await;
+    let _ = execute!(stdout(), DisableBracketedPaste, DisableMouseCapture);
     ratatui::restore();
 
     app_result
@@ -27,4 +41,3 @@ async fn main() -> Result<()> {
         Ok(())
     }
 }
-
@@ -0,0 +1,253 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{
+        Block, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, StatefulWidget,
+        Widget, Wrap,
+    },
+};
+use serde_json::json;
+
+// Lifecycle actions on a single server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    SoftReboot,
+    HardReboot,
+    Pause,
+    Unpause,
+    Suspend,
+    Resume,
+    Shelve,
+    Unshelve,
+    Lock,
+    Unlock,
+    Rescue,
+    Unrescue,
+    Delete,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::Start,
+        Action::Stop,
+        Action::SoftReboot,
+        Action::HardReboot,
+        Action::Pause,
+        Action::Unpause,
+        Action::Suspend,
+        Action::Resume,
+        Action::Shelve,
+        Action::Unshelve,
+        Action::Lock,
+        Action::Unlock,
+        Action::Rescue,
+        Action::Unrescue,
+        Action::Delete,
+    ];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Stop => "stop",
+            Action::SoftReboot => "reboot (soft)",
+            Action::HardReboot => "reboot (hard)",
+            Action::Pause => "pause",
+            Action::Unpause => "unpause",
+            Action::Suspend => "suspend",
+            Action::Resume => "resume",
+            Action::Shelve => "shelve",
+            Action::Unshelve => "unshelve",
+            Action::Lock => "lock",
+            Action::Unlock => "unlock",
+            Action::Rescue => "rescue",
+            Action::Unrescue => "unrescue",
+            Action::Delete => "delete",
+        }
+    }
+
+    // Actions that lose data or interrupt the guest ask before they run
+    pub const fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            Action::Stop | Action::HardReboot | Action::Shelve | Action::Rescue | Action::Delete
+        )
+    }
+
+    // Body for POST /servers/{id}/action, None for Delete which is a DELETE
+    pub fn body(&self) -> Option<serde_json::Value> {
+        let body = match self {
+            Action::Start => json!({ "os-start": null }),
+            Action::Stop => json!({ "os-stop": null }),
+            Action::SoftReboot => json!({ "reboot": { "type": "SOFT" } }),
+            Action::HardReboot => json!({ "reboot": { "type": "HARD" } }),
+            Action::Pause => json!({ "pause": null }),
+            Action::Unpause => json!({ "unpause": null }),
+            Action::Suspend => json!({ "suspend": null }),
+            Action::Resume => json!({ "resume": null }),
+            Action::Shelve => json!({ "shelve": null }),
+            Action::Unshelve => json!({ "unshelve": null }),
+            Action::Lock => json!({ "lock": null }),
+            Action::Unlock => json!({ "unlock": null }),
+            Action::Rescue => json!({ "rescue": {} }),
+            Action::Unrescue => json!({ "unrescue": null }),
+            Action::Delete => return None,
+        };
+        Some(body)
+    }
+}
+
+pub enum MenuResult {
+    Open,
+    Cancel,
+    Choose(Action),
+}
+
+// Popup listing the actions for the selected server
+#[derive(Debug, Clone)]
+pub struct ActionMenu {
+    server: String,
+    list_state: ListState,
+}
+
+impl ActionMenu {
+    pub fn new(server: String) -> Self {
+        Self {
+            server,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> MenuResult {
+        match key.code {
+            KeyCode::Esc => return MenuResult::Cancel,
+            KeyCode::Enter => {
+                // select_next only stops at the last item when the list is rendered
+                let index = self.list_state.selected().unwrap_or(0);
+                let action = Action::ALL[index.min(Action::ALL.len() - 1)];
+                return MenuResult::Choose(action);
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            _ => {}
+        }
+        MenuResult::Open
+    }
+}
+
+impl Widget for &mut ActionMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = popup_area(area, Action::ALL.len() as u16 + 2, 40);
+        let items: Vec<ListItem> = Action::ALL
+            .iter()
+            .map(|a| {
+                let line = Line::from(a.as_str());
+                ListItem::new(if a.is_destructive() { line.red() } else { line })
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(format!("Actions: {}", self.server))
+                    .title_bottom("Enter run, Esc cancel"),
+            )
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+            .highlight_style(Style::new().on_blue());
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
+
+// "Delete server web-1? (y/n)" before a destructive action runs
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    pub action: Action,
+    pub id: String,
+    pub name: String,
+}
+
+impl Confirm {
+    const MIN_WIDTH: u16 = 50;
+}
+
+impl Widget for &Confirm {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let question = Line::from(format!("{} server {}?", self.action.as_str(), self.name));
+        // as wide as the question, wrapped when the screen is narrower; (y/n) sits
+        // on the border so a long name cannot push it out
+        let width = (question.width() as u16 + 4)
+            .max(Confirm::MIN_WIDTH)
+            .min(area.width);
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let rows = question.width().div_ceil(inner).max(1) as u16;
+        let area = popup_area(area, rows + 2, width);
+        let block = Block::bordered()
+            .title("Confirm")
+            .title_bottom(Line::from("(y/n)").centered())
+            .red();
+        Clear.render(area, buf);
+        Paragraph::new(question.centered())
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
+
+fn popup_area(area: Rect, height: u16, width: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_body() {
+        assert_eq!(
+            Action::HardReboot.body(),
+            Some(json!({ "reboot": { "type": "HARD" } }))
+        );
+        assert_eq!(Action::Stop.body(), Some(json!({ "os-stop": null })));
+        assert_eq!(Action::Delete.body(), None);
+        assert!(Action::Delete.is_destructive());
+        assert!(!Action::Start.is_destructive());
+    }
+
+    #[test]
+    fn test_action_menu_stops_at_last_action() {
+        let mut menu = ActionMenu::new("web".to_string());
+        for _ in 0..Action::ALL.len() + 2 {
+            menu.handle_key(KeyEvent::from(KeyCode::Down));
+        }
+        let MenuResult::Choose(action) = menu.handle_key(KeyEvent::from(KeyCode::Enter)) else {
+            panic!("Enter should choose an action");
+        };
+        assert_eq!(action, *Action::ALL.last().unwrap());
+    }
+
+    #[test]
+    fn test_confirm_long_name() {
+        let confirm = Confirm {
+            action: Action::Delete,
+            id: "1".to_string(),
+            name: "x".repeat(80),
+        };
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        confirm.render(area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("(y/n)"));
+    }
+}
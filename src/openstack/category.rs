@@ -1,16 +1,30 @@
-#[derive(Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum Category {
     #[default]
     Identity,
     Compute,
+    Image,
+    Network,
 }
 
 impl Category {
-    pub fn from_type(type_: &str) -> Self {
+    // None for the services this client does not use (volume, object-store, ...)
+    pub fn from_type(type_: &str) -> Option<Self> {
         match type_ {
-            "identity" => Category::Identity,
-            "compute" => Category::Compute,
-            _ => Category::Identity,
+            "identity" => Some(Category::Identity),
+            "compute" => Some(Category::Compute),
+            "image" => Some(Category::Image),
+            "network" => Some(Category::Network),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Identity => "identity",
+            Category::Compute => "compute",
+            Category::Image => "image",
+            Category::Network => "network",
         }
     }
 }
@@ -0,0 +1,360 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, HighlightSpacing, List, ListItem, ListState, StatefulWidget, Widget},
+};
+use serde::{Deserialize, Serialize};
+
+// A column of the server table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Id,
+    Name,
+    Status,
+    TaskState,
+    VmState,
+    PowerState,
+    Flavor,
+    Image,
+    Ips,
+    AvailabilityZone,
+    Host,
+    KeyName,
+    Created,
+    Updated,
+}
+
+impl Column {
+    pub const ALL: [Column; 14] = [
+        Column::Id,
+        Column::Name,
+        Column::Status,
+        Column::TaskState,
+        Column::VmState,
+        Column::PowerState,
+        Column::Flavor,
+        Column::Image,
+        Column::Ips,
+        Column::AvailabilityZone,
+        Column::Host,
+        Column::KeyName,
+        Column::Created,
+        Column::Updated,
+    ];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Name => "Name",
+            Column::Status => "Status",
+            Column::TaskState => "Task State",
+            Column::VmState => "VM State",
+            Column::PowerState => "Power State",
+            Column::Flavor => "Flavor",
+            Column::Image => "Image",
+            Column::Ips => "IPs",
+            Column::AvailabilityZone => "AZ",
+            Column::Host => "Host",
+            Column::KeyName => "Key Name",
+            Column::Created => "Created",
+            Column::Updated => "Updated",
+        }
+    }
+
+    const fn default_width(&self) -> u16 {
+        match self {
+            Column::Id => 36,
+            Column::Name => 24,
+            Column::Status => 10,
+            Column::TaskState => 12,
+            Column::VmState => 10,
+            Column::PowerState => 11,
+            Column::Flavor => 16,
+            Column::Image => 20,
+            Column::Ips => 32,
+            Column::AvailabilityZone => 10,
+            Column::Host => 16,
+            Column::KeyName => 12,
+            Column::Created | Column::Updated => 16,
+        }
+    }
+
+    // Nova sort_key for GET /servers/detail, None where Nova can't sort by it
+    pub const fn sort_key(&self) -> Option<&'static str> {
+        match self {
+            Column::Id => Some("uuid"),
+            Column::Name => Some("display_name"),
+            Column::Status | Column::VmState => Some("vm_state"),
+            Column::TaskState => Some("task_state"),
+            Column::PowerState => Some("power_state"),
+            Column::Flavor => Some("instance_type_id"),
+            Column::Image => Some("image_ref"),
+            Column::Ips => None,
+            Column::AvailabilityZone => Some("availability_zone"),
+            Column::Host => Some("host"),
+            Column::KeyName => Some("key_name"),
+            Column::Created => Some("created_at"),
+            Column::Updated => Some("updated_at"),
+        }
+    }
+
+    const fn default_visible(&self) -> bool {
+        matches!(
+            self,
+            Column::Id | Column::Name | Column::Status | Column::TaskState | Column::Ips
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ColumnSetting {
+    pub column: Column,
+    pub width: u16,
+    pub visible: bool,
+}
+
+impl From<Column> for ColumnSetting {
+    fn from(column: Column) -> Self {
+        Self {
+            column,
+            width: column.default_width(),
+            visible: column.default_visible(),
+        }
+    }
+}
+
+// Column order, widths and visibility, saved with each profile
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Columns(pub Vec<ColumnSetting>);
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self(Column::ALL.into_iter().map(ColumnSetting::from).collect())
+    }
+}
+
+impl Columns {
+    pub const MIN_WIDTH: u16 = 4;
+    pub const MAX_WIDTH: u16 = 80;
+
+    pub fn visible(&self) -> Vec<ColumnSetting> {
+        self.0.iter().filter(|c| c.visible).copied().collect()
+    }
+
+    // Saved settings plus columns added since they were saved, hidden at the end
+    fn complete(&self) -> Self {
+        let mut settings = self.0.clone();
+        for column in Column::ALL {
+            if !settings.iter().any(|c| c.column == column) {
+                settings.push(ColumnSetting {
+                    visible: false,
+                    ..column.into()
+                });
+            }
+        }
+        Self(settings)
+    }
+}
+
+// Column the server table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl Sort {
+    // ("sort_key", ..), ("sort_dir", ..) for Nova, empty if it can't sort by the column
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        let Some(key) = self.column.sort_key() else {
+            return Vec::new();
+        };
+        let dir = if self.descending { "desc" } else { "asc" };
+        vec![("sort_key", key.to_string()), ("sort_dir", dir.to_string())]
+    }
+
+    // Header label with ▲ or ▼ when the table is sorted by `column`
+    pub fn label(sort: Option<Sort>, column: Column) -> String {
+        match sort {
+            Some(sort) if sort.column == column => {
+                let arrow = if sort.descending { "▼" } else { "▲" };
+                format!("{} {}", column.as_str(), arrow)
+            }
+            _ => column.as_str().to_string(),
+        }
+    }
+}
+
+pub enum EditorResult {
+    Editing,
+    Cancel,
+    Apply(Columns),
+}
+
+// Popup to show, hide, reorder and resize the server table columns
+#[derive(Debug, Clone)]
+pub struct ColumnEditor {
+    columns: Columns,
+    list_state: ListState,
+}
+
+impl ColumnEditor {
+    pub fn new(columns: &Columns) -> Self {
+        Self {
+            columns: columns.complete(),
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditorResult {
+        // select_next only stops at the last row when the list is rendered
+        let last = self.columns.0.len().saturating_sub(1);
+        let index = self.list_state.selected().unwrap_or(0).min(last);
+        self.list_state.select(Some(index));
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Esc => return EditorResult::Cancel,
+            KeyCode::Enter => return EditorResult::Apply(self.columns.clone()),
+            // move the selected column up or down in the table
+            KeyCode::Char('K') => self.move_column(index, -1),
+            KeyCode::Char('J') => self.move_column(index, 1),
+            KeyCode::Up if shift => self.move_column(index, -1),
+            KeyCode::Down if shift => self.move_column(index, 1),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char(' ') => {
+                if let Some(setting) = self.columns.0.get_mut(index) {
+                    setting.visible = !setting.visible;
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('>') | KeyCode::Right => self.resize(index, 1),
+            KeyCode::Char('-') | KeyCode::Char('<') | KeyCode::Left => self.resize(index, -1),
+            _ => {}
+        }
+        EditorResult::Editing
+    }
+
+    fn move_column(&mut self, index: usize, step: isize) {
+        let Some(target) = index.checked_add_signed(step) else {
+            return;
+        };
+        if target < self.columns.0.len() {
+            self.columns.0.swap(index, target);
+            self.list_state.select(Some(target));
+        }
+    }
+
+    fn resize(&mut self, index: usize, step: i16) {
+        let Some(setting) = self.columns.0.get_mut(index) else {
+            return;
+        };
+        setting.width = setting
+            .width
+            .saturating_add_signed(step)
+            .clamp(Columns::MIN_WIDTH, Columns::MAX_WIDTH);
+    }
+}
+
+impl Widget for &mut ColumnEditor {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = self.columns.0.len() as u16 + 2;
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let items: Vec<ListItem> = self
+            .columns
+            .0
+            .iter()
+            .map(|c| {
+                let mark = if c.visible { "[x]" } else { "[ ]" };
+                ListItem::new(Line::from_iter([
+                    format!("{} {:<14}", mark, c.column.as_str()).into(),
+                    format!("width {}", c.width).dim(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title("Columns")
+                    .title_bottom("Space show/hide, J/K move, +/- width, Enter save, Esc cancel"),
+            )
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+            .highlight_style(Style::new().on_blue());
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_columns_from_older_profile() {
+        let json = r#"[{"column": "name", "width": 30, "visible": true}]"#;
+        let columns: Columns = serde_json::from_str(json).unwrap();
+        assert_eq!(columns.visible().len(), 1);
+
+        // columns the profile doesn't know about yet are offered hidden
+        let complete = columns.complete();
+        assert_eq!(complete.0.len(), Column::ALL.len());
+        assert_eq!(complete.visible(), columns.visible());
+    }
+
+    #[test]
+    fn test_column_editor() {
+        let mut editor = ColumnEditor::new(&Columns::default());
+        // hide ID, widen Name and move it to the top
+        editor.handle_key(key(KeyCode::Char(' ')));
+        editor.handle_key(key(KeyCode::Char('j')));
+        editor.handle_key(key(KeyCode::Char('+')));
+        editor.handle_key(KeyEvent::new(KeyCode::Char('K'), KeyModifiers::SHIFT));
+        let EditorResult::Apply(columns) = editor.handle_key(key(KeyCode::Enter)) else {
+            panic!("expected Apply");
+        };
+        let first = columns.visible()[0];
+        assert_eq!(first.column, Column::Name);
+        assert_eq!(first.width, Column::Name.default_width() + 1);
+        assert!(
+            !columns
+                .0
+                .iter()
+                .any(|c| c.column == Column::Id && c.visible)
+        );
+    }
+
+    #[test]
+    fn test_column_editor_past_last_row() {
+        let mut editor = ColumnEditor::new(&Columns::default());
+        for _ in 0..Column::ALL.len() + 2 {
+            editor.handle_key(key(KeyCode::Down));
+        }
+        // acts on the last column instead of indexing past it
+        editor.handle_key(key(KeyCode::Char(' ')));
+        editor.handle_key(key(KeyCode::Char('+')));
+        let EditorResult::Apply(columns) = editor.handle_key(key(KeyCode::Enter)) else {
+            panic!("expected Apply");
+        };
+        let last = columns.0.last().unwrap();
+        assert_eq!(
+            last.visible,
+            !Columns::default().complete().0.last().unwrap().visible
+        );
+    }
+}
@@ -0,0 +1,777 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::field::{Field, SelectField, StringField};
+use crate::openstack::category::Category;
+use crate::openstack::image;
+use crate::openstack::microversion::Microversion;
+use crate::openstack::network;
+use crate::openstack::session::Session;
+
+// Nova rejects user data over 64 KiB once base64 encoded
+const MAX_USER_DATA: usize = 65535;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Name,
+    Flavor,
+    Source,
+    Network,
+    SecurityGroups,
+    KeyPair,
+    AvailabilityZone,
+    UserData,
+    Metadata,
+    Review,
+}
+
+impl Step {
+    const ALL: [Step; 10] = [
+        Step::Name,
+        Step::Flavor,
+        Step::Source,
+        Step::Network,
+        Step::SecurityGroups,
+        Step::KeyPair,
+        Step::AvailabilityZone,
+        Step::UserData,
+        Step::Metadata,
+        Step::Review,
+    ];
+
+    const fn as_str(&self) -> &'static str {
+        match self {
+            Step::Name => "Name",
+            Step::Flavor => "Flavor",
+            Step::Source => "Image or Boot Volume",
+            Step::Network => "Network",
+            Step::SecurityGroups => "Security Groups",
+            Step::KeyPair => "Key Pair",
+            Step::AvailabilityZone => "Availability Zone",
+            Step::UserData => "User Data",
+            Step::Metadata => "Metadata",
+            Step::Review => "Review",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Step::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+}
+
+// (ID, name) pairs and names the wizard offers, filled in by background requests
+#[derive(Debug, Default)]
+struct Choices {
+    flavors: Vec<(String, String)>,
+    images: Vec<(String, String)>,
+    networks: Vec<(String, String)>,
+    security_groups: Vec<String>,
+    keypairs: Vec<String>,
+    zones: Vec<String>,
+    errors: Vec<String>,
+    // bumped on every change so the select fields know to rebuild
+    generation: usize,
+}
+
+pub enum WizardResult {
+    Open,
+    Cancel,
+    Submit { name: String, body: Value },
+}
+
+// Multi-step form that boots a new server
+pub struct CreateServerWizard {
+    step: Step,
+    focus: usize,
+    message: String,
+    choices: Arc<RwLock<Choices>>,
+    generation: usize,
+    // the microversion the request is sent with, None means plain 2.1
+    compute_version: Option<Microversion>,
+    name: StringField,
+    flavor: SelectField,
+    source: SelectField,
+    image: SelectField,
+    volume: StringField,
+    network: SelectField,
+    port: StringField,
+    security_groups: StringField,
+    keypair: SelectField,
+    zone: SelectField,
+    user_data: StringField,
+    metadata: StringField,
+}
+
+const SOURCE_IMAGE: &str = "image";
+const SOURCE_VOLUME: &str = "volume";
+const NETWORK_DEFAULT: &str = "(default)";
+const KEYPAIR_NONE: &str = "(none)";
+const ZONE_ANY: &str = "(any)";
+
+impl Default for CreateServerWizard {
+    fn default() -> Self {
+        let mut security_groups = StringField::new("Security Groups".to_string());
+        security_groups.set_value("default".to_string());
+        Self {
+            step: Step::Name,
+            focus: 0,
+            message: String::new(),
+            choices: Arc::default(),
+            generation: 0,
+            compute_version: None,
+            name: StringField::new("Name".to_string()),
+            flavor: SelectField::new("Flavor".to_string(), Vec::new()),
+            source: SelectField::new(
+                "Boot From".to_string(),
+                vec![SOURCE_IMAGE.to_string(), SOURCE_VOLUME.to_string()],
+            ),
+            image: SelectField::new("Image".to_string(), Vec::new()),
+            volume: StringField::new("Boot Volume ID".to_string()),
+            network: SelectField::new("Network".to_string(), vec![NETWORK_DEFAULT.to_string()]),
+            port: StringField::new("Port ID".to_string()),
+            security_groups,
+            keypair: SelectField::new("Key Pair".to_string(), vec![KEYPAIR_NONE.to_string()]),
+            zone: SelectField::new("Availability Zone".to_string(), vec![ZONE_ANY.to_string()]),
+            user_data: StringField::new("User Data File".to_string()),
+            metadata: StringField::new("Metadata".to_string()),
+        }
+    }
+}
+
+impl CreateServerWizard {
+    // Start loading flavors, images, networks and the rest in the background
+    pub fn open(
+        compute_url: String,
+        image_url: Option<String>,
+        network_url: Option<String>,
+        session: Session,
+    ) -> Self {
+        let wizard = Self {
+            compute_version: session.compute_version(),
+            ..Self::default()
+        };
+        let choices = wizard.choices.clone();
+        tokio::spawn(load_choices(
+            choices,
+            compute_url,
+            image_url,
+            network_url,
+            session,
+        ));
+        wizard
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> WizardResult {
+        self.sync_choices();
+        match key.code {
+            KeyCode::Esc => {
+                if self.step == Step::Name {
+                    return WizardResult::Cancel;
+                }
+                self.go_to(Step::ALL[self.step.index() - 1]);
+            }
+            KeyCode::Tab => self.move_focus(1),
+            KeyCode::BackTab => self.move_focus(-1),
+            KeyCode::Enter => {
+                if let Err(e) = self.check_step() {
+                    self.message = e;
+                    return WizardResult::Open;
+                }
+                if self.step == Step::Review {
+                    return match self.request() {
+                        Ok(body) => WizardResult::Submit {
+                            name: self.name.value().trim().to_string(),
+                            body,
+                        },
+                        Err(e) => {
+                            self.message = e;
+                            WizardResult::Open
+                        }
+                    };
+                }
+                self.go_to(Step::ALL[self.step.index() + 1]);
+            }
+            _ => {
+                if let Some(field) = self.focused_mut() {
+                    field.on_key_press(key);
+                }
+            }
+        }
+        WizardResult::Open
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        if let Some(field) = self.focused_mut() {
+            field.paste(text);
+        }
+    }
+
+    fn go_to(&mut self, step: Step) {
+        self.step = step;
+        self.focus = 0;
+        self.message.clear();
+    }
+
+    fn move_focus(&mut self, step: isize) {
+        let len = self.fields().len().max(1) as isize;
+        self.focus = (self.focus as isize + step).rem_euclid(len) as usize;
+    }
+
+    fn fields(&self) -> Vec<&dyn Field> {
+        match self.step {
+            Step::Name => vec![&self.name],
+            Step::Flavor => vec![&self.flavor],
+            Step::Source if self.source.value() == SOURCE_VOLUME => {
+                vec![&self.source, &self.volume]
+            }
+            Step::Source => vec![&self.source, &self.image],
+            Step::Network => vec![&self.network, &self.port],
+            Step::SecurityGroups => vec![&self.security_groups],
+            Step::KeyPair => vec![&self.keypair],
+            Step::AvailabilityZone => vec![&self.zone],
+            Step::UserData => vec![&self.user_data],
+            Step::Metadata => vec![&self.metadata],
+            Step::Review => vec![],
+        }
+    }
+
+    fn focused_mut(&mut self) -> Option<&mut dyn Field> {
+        let volume = self.source.value() == SOURCE_VOLUME;
+        let field: &mut dyn Field = match (self.step, self.focus) {
+            (Step::Name, _) => &mut self.name,
+            (Step::Flavor, _) => &mut self.flavor,
+            (Step::Source, 0) => &mut self.source,
+            (Step::Source, _) if volume => &mut self.volume,
+            (Step::Source, _) => &mut self.image,
+            (Step::Network, 0) => &mut self.network,
+            (Step::Network, _) => &mut self.port,
+            (Step::SecurityGroups, _) => &mut self.security_groups,
+            (Step::KeyPair, _) => &mut self.keypair,
+            (Step::AvailabilityZone, _) => &mut self.zone,
+            (Step::UserData, _) => &mut self.user_data,
+            (Step::Metadata, _) => &mut self.metadata,
+            (Step::Review, _) => return None,
+        };
+        Some(field)
+    }
+
+    // Copy newly loaded choices into the select fields, keeping the current picks
+    fn sync_choices(&mut self) {
+        let choices = self.choices.read().unwrap();
+        if choices.generation == self.generation {
+            return;
+        }
+        self.generation = choices.generation;
+        let names = |pairs: &[(String, String)]| pairs.iter().map(|(_, n)| n.clone()).collect();
+        set_options(&mut self.flavor, names(&choices.flavors));
+        set_options(&mut self.image, names(&choices.images));
+        set_options(
+            &mut self.network,
+            std::iter::once(NETWORK_DEFAULT.to_string())
+                .chain(choices.networks.iter().map(|(_, n)| n.clone()))
+                .collect(),
+        );
+        set_options(
+            &mut self.keypair,
+            std::iter::once(KEYPAIR_NONE.to_string())
+                .chain(choices.keypairs.iter().cloned())
+                .collect(),
+        );
+        set_options(
+            &mut self.zone,
+            std::iter::once(ZONE_ANY.to_string())
+                .chain(choices.zones.iter().cloned())
+                .collect(),
+        );
+    }
+
+    fn check_step(&self) -> Result<(), String> {
+        match self.step {
+            Step::Name if self.name.value().trim().is_empty() => Err("Name is required".into()),
+            Step::Flavor if self.flavor.options.is_empty() => Err("No flavors loaded yet".into()),
+            Step::Source if self.source.value() == SOURCE_VOLUME && self.volume.is_empty() => {
+                Err("Boot volume ID is required".into())
+            }
+            Step::Source
+                if self.source.value() == SOURCE_IMAGE && self.image.options.is_empty() =>
+            {
+                Err("No images loaded yet".into())
+            }
+            Step::UserData => self.user_data().map(|_| ()),
+            Step::Metadata => parse_metadata(self.metadata.value()).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    fn user_data(&self) -> Result<Option<String>, String> {
+        let path = self.user_data.value().trim();
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let data = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        let encoded = STANDARD.encode(&data);
+        if encoded.len() > MAX_USER_DATA {
+            return Err(format!("{} is too large for user data", path));
+        }
+        Ok(Some(encoded))
+    }
+
+    // Body for POST /servers
+    fn request(&self) -> Result<Value, String> {
+        let choices = self.choices.read().unwrap();
+        let pick = |pairs: &[(String, String)], field: &SelectField| {
+            pairs
+                .get(field.index)
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| format!("No {} selected", field.value()))
+        };
+
+        let mut server = json!({
+            "name": self.name.value().trim(),
+            "flavorRef": pick(&choices.flavors, &self.flavor)?,
+        });
+        if self.source.value() == SOURCE_VOLUME {
+            server["block_device_mapping_v2"] = json!([{
+                "boot_index": 0,
+                "uuid": self.volume.value().trim(),
+                "source_type": "volume",
+                "destination_type": "volume",
+                "delete_on_termination": false,
+            }]);
+        } else {
+            server["imageRef"] = json!(pick(&choices.images, &self.image)?);
+        }
+
+        let port = self.port.value().trim();
+        if !port.is_empty() {
+            server["networks"] = json!([{ "port": port }]);
+        } else if self.network.index > 0 {
+            let (id, _) = &choices.networks[self.network.index - 1];
+            server["networks"] = json!([{ "uuid": id }]);
+        } else if self
+            .compute_version
+            .is_some_and(|v| v >= Microversion::AUTO_NETWORKS)
+        {
+            server["networks"] = json!("auto");
+        }
+
+        let groups: Vec<Value> = split_list(self.security_groups.value())
+            .map(|name| json!({ "name": name }))
+            .collect();
+        if !groups.is_empty() {
+            server["security_groups"] = json!(groups);
+        }
+        if self.keypair.index > 0 {
+            server["key_name"] = json!(self.keypair.value());
+        }
+        if self.zone.index > 0 {
+            server["availability_zone"] = json!(self.zone.value());
+        }
+        if let Some(user_data) = self.user_data()? {
+            server["user_data"] = json!(user_data);
+        }
+        let metadata = parse_metadata(self.metadata.value())?;
+        if !metadata.is_empty() {
+            server["metadata"] = json!(metadata);
+        }
+        Ok(json!({ "server": server }))
+    }
+
+    fn review(&self) -> Vec<Line<'static>> {
+        let source = if self.source.value() == SOURCE_VOLUME {
+            format!("volume {}", self.volume.value())
+        } else {
+            format!("image {}", self.image.value())
+        };
+        let network = if self.port.is_empty() {
+            self.network.value().to_string()
+        } else {
+            format!("port {}", self.port.value())
+        };
+        [
+            ("Name", self.name.value().to_string()),
+            ("Flavor", self.flavor.value().to_string()),
+            ("Boot From", source),
+            ("Network", network),
+            ("Security Groups", self.security_groups.value().to_string()),
+            ("Key Pair", self.keypair.value().to_string()),
+            ("Availability Zone", self.zone.value().to_string()),
+            ("User Data File", self.user_data.value().to_string()),
+            ("Metadata", self.metadata.value().to_string()),
+        ]
+        .into_iter()
+        .map(|(label, value)| Line::from_iter([format!("{}: ", label).bold(), value.into()]))
+        .collect()
+    }
+
+    // Names the user can type into free-text steps
+    fn hint(&self) -> String {
+        let choices = self.choices.read().unwrap();
+        match self.step {
+            Step::SecurityGroups if !choices.security_groups.is_empty() => format!(
+                "Comma separated. Available: {}",
+                choices.security_groups.join(", ")
+            ),
+            Step::Metadata => "key=value pairs, comma separated".to_string(),
+            Step::UserData => "Path to a cloud-init file, optional".to_string(),
+            Step::Network => "A port ID takes precedence over the network".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Widget for &mut CreateServerWizard {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.sync_choices();
+        let title = format!(
+            "Create Server - Step {}/{}: {}",
+            self.step.index() + 1,
+            Step::ALL.len(),
+            self.step.as_str()
+        );
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom("Tab next field, ←/→ change, Enter next step, Esc back");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ]);
+        let [message_area, hint_area, body_area] = inner.layout(&layout);
+        let errors = self.choices.read().unwrap().errors.join("; ");
+        let message = if self.message.is_empty() {
+            errors.red()
+        } else {
+            self.message.clone().red()
+        };
+        Line::from(message).render(message_area, buf);
+        Line::from(self.hint()).dim().render(hint_area, buf);
+
+        if self.step == Step::Review {
+            let mut lines = self.review();
+            lines.push(Line::default());
+            lines.push(Line::from("Press Enter to create the server").bold());
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .render(body_area, buf);
+            return;
+        }
+        let fields = self.fields();
+        let rows = Layout::vertical(
+            std::iter::repeat_n(Constraint::Length(1), fields.len()).chain([Constraint::Fill(1)]),
+        );
+        let areas = body_area.layout_vec(&rows);
+        for (index, (field, area)) in fields.iter().zip(&areas).enumerate() {
+            field.render_line(*area, buf);
+            if index == self.focus {
+                Line::from("<").right_aligned().bold().render(*area, buf);
+            }
+        }
+    }
+}
+
+impl CreateServerWizard {
+    // Cursor position for the focused text field, if any
+    pub fn cursor(&self, area: Rect) -> Option<(u16, u16)> {
+        if self.step == Step::Review {
+            return None;
+        }
+        let inner = Block::bordered().inner(area);
+        let field = self.fields().into_iter().nth(self.focus)?;
+        let offset = field.cursor_offset(inner.width);
+        let row = inner.y + 2 + self.focus as u16;
+        Some(((inner.x as i32 + offset.x) as u16, row))
+    }
+}
+
+fn set_options(field: &mut SelectField, options: Vec<String>) {
+    let current = field.value().to_string();
+    field.options = options;
+    field.index = field
+        .options
+        .iter()
+        .position(|o| *o == current)
+        .unwrap_or(0);
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn parse_metadata(value: &str) -> Result<BTreeMap<String, String>, String> {
+    split_list(value)
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Metadata {} is not key=value", pair))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+async fn load_choices(
+    choices: Arc<RwLock<Choices>>,
+    compute_url: String,
+    image_url: Option<String>,
+    network_url: Option<String>,
+    session: Session,
+) {
+    let update = |apply: &dyn Fn(&mut Choices)| {
+        let mut choices = choices.write().unwrap();
+        apply(&mut choices);
+        choices.generation += 1;
+    };
+    let error = |what: &str, err: anyhow::Error| {
+        let message = format!("Loading {} failed: {}", what, err);
+        update(&|c| c.errors.push(message.clone()));
+    };
+
+    match list_flavors(&compute_url, &session).await {
+        Ok(flavors) => update(&|c| {
+            c.flavors = flavors
+                .iter()
+                .map(|f| (f.id.clone(), f.name.clone()))
+                .collect()
+        }),
+        Err(err) => error("flavors", err),
+    }
+    match &image_url {
+        Some(url) => match image::list_images(url, &session).await {
+            Ok(images) => update(&|c| {
+                c.images = images
+                    .iter()
+                    .map(|i| (i.id.clone(), i.display_name()))
+                    .collect()
+            }),
+            Err(err) => error("images", err),
+        },
+        None => error("images", anyhow!("no image endpoint in the catalog")),
+    }
+    if let Some(url) = &network_url {
+        match network::list_networks(url, &session).await {
+            Ok(networks) => update(&|c| {
+                c.networks = networks
+                    .iter()
+                    .map(|n| (n.id.clone(), n.name.clone()))
+                    .collect()
+            }),
+            Err(err) => error("networks", err),
+        }
+        match network::list_security_groups(url, &session).await {
+            Ok(groups) => {
+                update(&|c| c.security_groups = groups.iter().map(|g| g.name.clone()).collect())
+            }
+            Err(err) => error("security groups", err),
+        }
+    }
+    match list_keypairs(&compute_url, &session).await {
+        Ok(keypairs) => update(&|c| c.keypairs = keypairs.clone()),
+        Err(err) => error("key pairs", err),
+    }
+    match list_availability_zones(&compute_url, &session).await {
+        Ok(zones) => update(&|c| c.zones = zones.clone()),
+        Err(err) => error("availability zones", err),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct FlavorsResponse {
+    flavors: Vec<FlavorSummary>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FlavorSummary {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct KeypairsResponse {
+    keypairs: Vec<KeypairEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KeypairEntry {
+    keypair: Keypair,
+}
+
+#[derive(Deserialize, Debug)]
+struct Keypair {
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AvailabilityZonesResponse {
+    #[serde(rename = "availabilityZoneInfo")]
+    zones: Vec<AvailabilityZone>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AvailabilityZone {
+    #[serde(rename = "zoneName")]
+    name: String,
+    #[serde(rename = "zoneState")]
+    state: ZoneState,
+}
+
+#[derive(Deserialize, Debug)]
+struct ZoneState {
+    available: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct CreateServerResponse {
+    server: CreatedServer,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreatedServer {
+    pub id: String,
+}
+
+// フレーバー一覧取得
+async fn list_flavors(url: &str, session: &Session) -> Result<Vec<FlavorSummary>> {
+    let url = format!("{}/flavors", url.trim_end_matches('/'));
+    let body: FlavorsResponse = session.get_json(Category::Compute, &url).await?;
+    Ok(body.flavors)
+}
+
+// キーペア一覧取得
+async fn list_keypairs(url: &str, session: &Session) -> Result<Vec<String>> {
+    let url = format!("{}/os-keypairs", url.trim_end_matches('/'));
+    let body: KeypairsResponse = session.get_json(Category::Compute, &url).await?;
+    Ok(body.keypairs.into_iter().map(|k| k.keypair.name).collect())
+}
+
+// アベイラビリティゾーン一覧取得
+async fn list_availability_zones(url: &str, session: &Session) -> Result<Vec<String>> {
+    let url = format!("{}/os-availability-zone", url.trim_end_matches('/'));
+    let body: AvailabilityZonesResponse = session.get_json(Category::Compute, &url).await?;
+    Ok(body
+        .zones
+        .into_iter()
+        .filter(|z| z.state.available)
+        .map(|z| z.name)
+        .collect())
+}
+
+// サーバー作成
+pub async fn create_server(url: &str, session: &Session, body: &Value) -> Result<CreatedServer> {
+    let url = format!("{}/servers", url.trim_end_matches('/'));
+    let resp = session
+        .send(Category::Compute, |client| client.post(&url).json(body))
+        .await?;
+    let body = resp.json::<CreateServerResponse>().await?;
+    Ok(body.server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, crossterm::event::KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = parse_metadata("role=web, tier = frontend").unwrap();
+        assert_eq!(metadata["role"], "web");
+        assert_eq!(metadata["tier"], "frontend");
+        assert!(parse_metadata("role").is_err());
+        assert!(parse_metadata("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_wizard_request() {
+        let mut wizard = CreateServerWizard::default();
+        {
+            let mut choices = wizard.choices.write().unwrap();
+            choices.flavors = vec![
+                ("1".into(), "m1.tiny".into()),
+                ("2".into(), "m1.small".into()),
+            ];
+            choices.images = vec![("img-1".into(), "cirros".into())];
+            choices.networks = vec![("net-1".into(), "private".into())];
+            choices.keypairs = vec!["mykey".into()];
+            choices.generation = 1;
+        }
+
+        // the name is required before moving on
+        wizard.handle_key(key(KeyCode::Enter));
+        assert_eq!(wizard.step, Step::Name);
+        wizard.paste("web-1");
+        wizard.handle_key(key(KeyCode::Enter));
+
+        wizard.handle_key(key(KeyCode::Right));
+        wizard.handle_key(key(KeyCode::Enter));
+        assert_eq!(wizard.step, Step::Source);
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.handle_key(key(KeyCode::Right));
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.handle_key(key(KeyCode::Right));
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.handle_key(key(KeyCode::Enter));
+        wizard.paste("role=web");
+        wizard.handle_key(key(KeyCode::Enter));
+        assert_eq!(wizard.step, Step::Review);
+
+        let WizardResult::Submit { name, body } = wizard.handle_key(key(KeyCode::Enter)) else {
+            panic!("expected Submit");
+        };
+        assert_eq!(name, "web-1");
+        assert_eq!(
+            body,
+            json!({
+                "server": {
+                    "name": "web-1",
+                    "flavorRef": "2",
+                    "imageRef": "img-1",
+                    "networks": [{ "uuid": "net-1" }],
+                    "security_groups": [{ "name": "default" }],
+                    "key_name": "mykey",
+                    "metadata": { "role": "web" },
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_wizard_request_auto_network() {
+        let mut wizard = CreateServerWizard {
+            compute_version: Some(Microversion::new(2, 37)),
+            ..CreateServerWizard::default()
+        };
+        {
+            let mut choices = wizard.choices.write().unwrap();
+            choices.flavors = vec![("1".into(), "m1.tiny".into())];
+            choices.images = vec![("img-1".into(), "cirros".into())];
+        }
+        wizard.name.set_value("web-1".to_string());
+        let body = wizard.request().unwrap();
+        assert_eq!(body["server"]["networks"], "auto");
+
+        // 2.1 leaves the network to Nova
+        wizard.compute_version = None;
+        let body = wizard.request().unwrap();
+        assert!(body["server"].get("networks").is_none());
+    }
+}
@@ -0,0 +1,332 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+use reqwest::{Response, StatusCode};
+use std::fmt;
+
+use crate::openstack::category::Category;
+
+// Why a call to an OpenStack API failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    // a 4xx or 5xx answer
+    Api {
+        service: Category,
+        status: StatusCode,
+        request_id: Option<String>,
+        // "itemNotFound", "NetworkNotFound", ...
+        fault: Option<String>,
+        message: Option<String>,
+    },
+    // Keystone refused the credentials, or the token even after re-issuing it
+    Auth {
+        status: StatusCode,
+        request_id: Option<String>,
+        message: Option<String>,
+    },
+    Timeout {
+        service: Category,
+    },
+    // certificate verification or handshake
+    Tls {
+        service: Category,
+        message: String,
+    },
+    // DNS, refused or reset connections
+    Network {
+        service: Category,
+        message: String,
+    },
+}
+
+impl Error {
+    // Turns a 4xx or 5xx answer into an Error, other answers are returned as is
+    pub async fn check(service: Category, resp: Response) -> anyhow::Result<Response> {
+        let status = resp.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(resp);
+        }
+        let request_id = request_id(&resp);
+        let body = resp.text().await.unwrap_or_default();
+        Err(Self::from_body(service, status, request_id, &body).into())
+    }
+
+    // Nova: {"itemNotFound": {"message": ..., "code": 404}}
+    // Neutron: {"NeutronError": {"type": "NetworkNotFound", "message": ...}}
+    // Keystone: {"error": {"code": 401, "title": "Unauthorized", "message": ...}}
+    // Glance answers with plain text, which is left out
+    fn from_body(
+        service: Category,
+        status: StatusCode,
+        request_id: Option<String>,
+        body: &str,
+    ) -> Self {
+        let value: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let fault = value.as_object().and_then(|object| {
+            object.iter().find_map(|(key, fault)| {
+                let message = fault.get("message")?.as_str()?;
+                let name = fault
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or(key.as_str());
+                Some((name.to_string(), message.to_string()))
+            })
+        });
+        let (fault, message) = fault.unzip();
+        if status == StatusCode::UNAUTHORIZED {
+            return Self::Auth {
+                status,
+                request_id,
+                message,
+            };
+        }
+        Self::Api {
+            service,
+            status,
+            request_id,
+            fault,
+            message,
+        }
+    }
+
+    // Sorts out why a request got no answer at all
+    pub fn from_reqwest(service: Category, err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            return Self::Timeout { service };
+        }
+        // native-tls reports certificate problems somewhere down the source chain
+        let mut chain = Vec::new();
+        let mut source: Option<&dyn std::error::Error> = Some(&err);
+        while let Some(e) = source {
+            chain.push(e.to_string());
+            source = e.source();
+        }
+        let message = chain.last().cloned().unwrap_or_default();
+        let tls = chain.iter().any(|m| {
+            let m = m.to_lowercase();
+            m.contains("certificate") || m.contains("tls") || m.contains("ssl")
+        });
+        if tls {
+            Self::Tls { service, message }
+        } else {
+            Self::Network { service, message }
+        }
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Api { status, .. } | Self::Auth { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Self::Api { request_id, .. } | Self::Auth { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Api { .. } => "API error",
+            Self::Auth { .. } => "Authentication failed",
+            Self::Timeout { .. } => "Timed out",
+            Self::Tls { .. } => "TLS error",
+            Self::Network { .. } => "Connection failed",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Api {
+                service,
+                status,
+                fault: Some(fault),
+                message: Some(message),
+                ..
+            } => write!(f, "{} {}: {}: {}", service.as_str(), status, fault, message),
+            Self::Api {
+                service, status, ..
+            } => write!(f, "{} {}: Unexpected status", service.as_str(), status),
+            Self::Auth {
+                status, message, ..
+            } => match message {
+                Some(message) => write!(f, "Authentication failed ({}): {}", status, message),
+                None => write!(f, "Authentication failed ({})", status),
+            },
+            Self::Timeout { service } => write!(f, "{} request timed out", service.as_str()),
+            Self::Tls { service, message } => write!(
+                f,
+                "TLS error talking to {}: {} (check CA Bundle / Skip TLS Verify)",
+                service.as_str(),
+                message
+            ),
+            Self::Network { service, message } => {
+                write!(f, "Cannot reach {}: {}", service.as_str(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Nova, Neutron, Glance and Keystone all send the generic header; older Nova only its own
+fn request_id(resp: &Response) -> Option<String> {
+    ["x-openstack-request-id", "x-compute-request-id"]
+        .iter()
+        .find_map(|name| resp.headers().get(*name)?.to_str().ok())
+        .map(str::to_string)
+}
+
+// A failure shown in the error panel until it is dismissed
+#[derive(Debug, Clone)]
+pub struct Report {
+    // what was being done, "Loading servers"
+    pub context: String,
+    pub error: Option<Error>,
+    pub message: String,
+    pub at: DateTime<Local>,
+}
+
+impl Report {
+    const WIDTH: u16 = 72;
+
+    pub fn new(context: impl Into<String>, err: &anyhow::Error) -> Self {
+        Self {
+            context: context.into(),
+            error: err.downcast_ref::<Error>().cloned(),
+            message: err.to_string(),
+            at: Local::now(),
+        }
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.error.as_ref().and_then(Error::request_id)
+    }
+
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(format!("{} failed", self.context)).bold(),
+            Line::from(self.message.clone()),
+        ];
+        if let Some(error) = &self.error {
+            let mut kind = error.kind().to_string();
+            if let Some(status) = error.status() {
+                kind.push_str(&format!(", HTTP {}", status.as_u16()));
+            }
+            lines.push(Line::from(kind).dim());
+        }
+        if let Some(id) = self.request_id() {
+            // what the cloud operators need to find the request in their logs
+            lines.push(Line::from(format!("Request ID: {}", id)).yellow());
+        }
+        lines
+    }
+
+    // Bottom right corner of `area`, tall enough for the wrapped lines
+    fn area(&self, area: Rect) -> Rect {
+        let width = Self::WIDTH.min(area.width);
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let rows: usize = self
+            .lines()
+            .iter()
+            .map(|line| line.width().div_ceil(inner).max(1))
+            .sum();
+        let [area] = Layout::vertical([Constraint::Length(rows as u16 + 2)])
+            .flex(Flex::End)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::End)
+            .areas(area);
+        area
+    }
+}
+
+impl Widget for &Report {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = self.area(area);
+        let block = Block::bordered()
+            .red()
+            .title(format!("Error {}", self.at.format("%H:%M:%S")))
+            .title_bottom("Esc to dismiss");
+        Clear.render(area, buf);
+        Paragraph::new(self.lines())
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_body() {
+        let error = Error::from_body(
+            Category::Compute,
+            StatusCode::NOT_FOUND,
+            Some("req-1234".to_string()),
+            r#"{"itemNotFound": {"message": "Instance abc could not be found.", "code": 404}}"#,
+        );
+        assert_eq!(
+            error.to_string(),
+            "compute 404 Not Found: itemNotFound: Instance abc could not be found."
+        );
+        assert_eq!(error.request_id(), Some("req-1234"));
+
+        let error = Error::from_body(
+            Category::Network,
+            StatusCode::NOT_FOUND,
+            None,
+            r#"{"NeutronError": {"type": "NetworkNotFound", "message": "Network x could not be found.", "detail": ""}}"#,
+        );
+        assert!(matches!(error, Error::Api { fault: Some(ref f), .. } if f == "NetworkNotFound"));
+
+        let error = Error::from_body(
+            Category::Image,
+            StatusCode::SERVICE_UNAVAILABLE,
+            None,
+            "<html>busy</html>",
+        );
+        assert_eq!(
+            error.to_string(),
+            "image 503 Service Unavailable: Unexpected status"
+        );
+
+        let error = Error::from_body(
+            Category::Identity,
+            StatusCode::UNAUTHORIZED,
+            None,
+            r#"{"error": {"code": 401, "title": "Unauthorized", "message": "The request you have made requires authentication."}}"#,
+        );
+        assert!(matches!(error, Error::Auth { .. }));
+    }
+
+    #[test]
+    fn test_report_lines() {
+        let err = anyhow::Error::from(Error::from_body(
+            Category::Compute,
+            StatusCode::CONFLICT,
+            Some("req-5678".to_string()),
+            r#"{"conflictingRequest": {"message": "Cannot 'reboot' instance while it is in task_state rebooting", "code": 409}}"#,
+        ));
+        let report = Report::new("reboot", &err);
+        let lines: Vec<String> = report.lines().iter().map(Line::to_string).collect();
+        assert_eq!(lines[0], "reboot failed");
+        assert_eq!(lines[2], "API error, HTTP 409");
+        assert_eq!(lines[3], "Request ID: req-5678");
+
+        // errors from outside the API have no request ID
+        let report = Report::new("Loading servers", &anyhow::anyhow!("bad JSON"));
+        assert_eq!(report.lines().len(), 2);
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::DateTime;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Widget},
+};
+
+use crate::field::{Field, SelectField, StringField};
+
+const ANY: &str = "(any)";
+const STATUSES: [&str; 12] = [
+    ANY,
+    "ACTIVE",
+    "BUILD",
+    "ERROR",
+    "HARD_REBOOT",
+    "PAUSED",
+    "REBOOT",
+    "RESCUE",
+    "RESIZE",
+    "SHELVED_OFFLOADED",
+    "SHUTOFF",
+    "SUSPENDED",
+];
+
+// Query parameters for GET /servers/detail, applied by Nova
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filters {
+    pub status: String,
+    // regular expression, matched by the database
+    pub name: String,
+    pub image: String,
+    pub flavor: String,
+    pub ip: String,
+    // ISO 8601 time, servers updated since then
+    pub changes_since: String,
+    // servers of every project, admin only
+    pub all_tenants: bool,
+}
+
+impl Filters {
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query: Vec<(&'static str, String)> = [
+            ("status", &self.status),
+            ("name", &self.name),
+            ("image", &self.image),
+            ("flavor", &self.flavor),
+            ("ip", &self.ip),
+            ("changes-since", &self.changes_since),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key, value.clone()))
+        .collect();
+        if self.all_tenants {
+            query.push(("all_tenants", "1".to_string()));
+        }
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.query().is_empty()
+    }
+
+    // "status=ACTIVE name=web" for the title bar
+    pub fn summary(&self) -> String {
+        self.query()
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+pub enum PanelResult {
+    Open,
+    Cancel,
+    Apply(Filters),
+}
+
+// Popup editing the Nova side filters of the server list
+pub struct FilterPanel {
+    status: SelectField,
+    name: StringField,
+    image: StringField,
+    flavor: StringField,
+    ip: StringField,
+    changes_since: StringField,
+    all_tenants: SelectField,
+    focus: usize,
+    message: String,
+}
+
+impl FilterPanel {
+    const FIELDS: usize = 7;
+
+    pub fn new(filters: &Filters) -> Self {
+        let mut panel = Self {
+            status: SelectField::new(
+                "Status".to_string(),
+                STATUSES.iter().map(|s| s.to_string()).collect(),
+            ),
+            name: StringField::new("Name (regex)".to_string()),
+            image: StringField::new("Image ID".to_string()),
+            flavor: StringField::new("Flavor ID".to_string()),
+            ip: StringField::new("IP (regex)".to_string()),
+            changes_since: StringField::new("Changes Since".to_string()),
+            all_tenants: SelectField::new(
+                "All Projects".to_string(),
+                vec!["no".to_string(), "yes".to_string()],
+            ),
+            focus: 0,
+            message: String::new(),
+        };
+        panel.status.index = STATUSES
+            .iter()
+            .position(|s| *s == filters.status)
+            .unwrap_or(0);
+        panel.name.set_value(filters.name.clone());
+        panel.image.set_value(filters.image.clone());
+        panel.flavor.set_value(filters.flavor.clone());
+        panel.ip.set_value(filters.ip.clone());
+        panel.changes_since.set_value(filters.changes_since.clone());
+        panel.all_tenants.index = usize::from(filters.all_tenants);
+        panel
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PanelResult {
+        match key.code {
+            KeyCode::Esc => return PanelResult::Cancel,
+            KeyCode::Tab | KeyCode::Down => self.focus = (self.focus + 1) % Self::FIELDS,
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focus = (self.focus + Self::FIELDS - 1) % Self::FIELDS
+            }
+            KeyCode::Enter => match self.filters() {
+                Ok(filters) => return PanelResult::Apply(filters),
+                Err(e) => self.message = e,
+            },
+            _ => self.field_mut(self.focus).on_key_press(key),
+        }
+        PanelResult::Open
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.field_mut(self.focus).paste(text);
+    }
+
+    fn fields(&self) -> [&dyn Field; Self::FIELDS] {
+        [
+            &self.status,
+            &self.name,
+            &self.image,
+            &self.flavor,
+            &self.ip,
+            &self.changes_since,
+            &self.all_tenants,
+        ]
+    }
+
+    fn field_mut(&mut self, index: usize) -> &mut dyn Field {
+        match index {
+            0 => &mut self.status,
+            1 => &mut self.name,
+            2 => &mut self.image,
+            3 => &mut self.flavor,
+            4 => &mut self.ip,
+            5 => &mut self.changes_since,
+            _ => &mut self.all_tenants,
+        }
+    }
+
+    fn filters(&self) -> Result<Filters, String> {
+        let changes_since = self.changes_since.value().trim();
+        if !changes_since.is_empty() && DateTime::parse_from_rfc3339(changes_since).is_err() {
+            return Err("Changes Since must look like 2024-05-01T00:00:00Z".to_string());
+        }
+        let status = self.status.value();
+        Ok(Filters {
+            status: if status == ANY { "" } else { status }.to_string(),
+            name: self.name.value().trim().to_string(),
+            image: self.image.value().trim().to_string(),
+            flavor: self.flavor.value().trim().to_string(),
+            ip: self.ip.value().trim().to_string(),
+            changes_since: changes_since.to_string(),
+            all_tenants: self.all_tenants.index == 1,
+        })
+    }
+
+    fn area(area: Rect) -> Rect {
+        let [area] = Layout::vertical([Constraint::Length(Self::FIELDS as u16 + 3)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        area
+    }
+
+    // Cursor position inside the focused text field
+    pub fn cursor(&self, area: Rect) -> (u16, u16) {
+        let inner = Block::bordered().inner(Self::area(area));
+        let row = Rect {
+            y: inner.y + 1 + self.focus as u16,
+            height: 1,
+            ..inner
+        };
+        let position = row.offset(self.fields()[self.focus].cursor_offset(row.width));
+        (position.x, position.y)
+    }
+}
+
+impl Widget for &FilterPanel {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = FilterPanel::area(area);
+        let block = Block::bordered()
+            .title("Filters")
+            .title_bottom("Tab move, ←/→ change, Enter apply, Esc cancel");
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let rows = Layout::vertical(std::iter::repeat_n(
+            Constraint::Length(1),
+            FilterPanel::FIELDS + 1,
+        ));
+        let areas = inner.layout_vec(&rows);
+        Line::from(self.message.clone()).red().render(areas[0], buf);
+        for (index, field) in self.fields().iter().enumerate() {
+            let area = areas[index + 1];
+            field.render_line(area, buf);
+            if index == self.focus {
+                Line::from("<").right_aligned().bold().render(area, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters_query() {
+        let mut panel = FilterPanel::new(&Filters::default());
+        panel.handle_key(KeyEvent::from(KeyCode::Right));
+        panel.handle_key(KeyEvent::from(KeyCode::Tab));
+        panel.paste("^web-");
+        panel.handle_key(KeyEvent::from(KeyCode::BackTab));
+        panel.handle_key(KeyEvent::from(KeyCode::BackTab));
+        panel.handle_key(KeyEvent::from(KeyCode::Right));
+        let PanelResult::Apply(filters) = panel.handle_key(KeyEvent::from(KeyCode::Enter)) else {
+            panic!("expected Apply");
+        };
+        assert_eq!(
+            filters.query(),
+            [
+                ("status", "ACTIVE".to_string()),
+                ("name", "^web-".to_string()),
+                ("all_tenants", "1".to_string()),
+            ]
+        );
+        assert_eq!(filters.summary(), "status=ACTIVE name=^web- all_tenants=1");
+
+        // a malformed time keeps the panel open
+        let mut panel = FilterPanel::new(&filters);
+        for _ in 0..5 {
+            panel.handle_key(KeyEvent::from(KeyCode::Tab));
+        }
+        panel.paste("yesterday");
+        assert!(matches!(
+            panel.handle_key(KeyEvent::from(KeyCode::Enter)),
+            PanelResult::Open
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::openstack::category::Category;
+use crate::openstack::session::Session;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Image {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Image {
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.clone())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ImagesResponse {
+    images: Vec<Image>,
+    // path of the next page, e.g. /v2/images?marker=...
+    #[serde(default)]
+    next: Option<String>,
+}
+
+// Glance catalog entries usually have no version, some end in /v2
+fn image_root(url: &str) -> &str {
+    url.trim_end_matches('/').trim_end_matches("/v2")
+}
+
+// イメージ一覧取得 (Glance v2)
+pub async fn list_images(url: &str, session: &Session) -> Result<Vec<Image>> {
+    let root = image_root(url);
+    let mut images = Vec::new();
+    let mut next = Some("/v2/images?status=active".to_string());
+    while let Some(path) = next {
+        let page: ImagesResponse = session
+            .get_json(Category::Image, &format!("{}{}", root, path))
+            .await?;
+        images.extend(page.images);
+        next = page.next;
+    }
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_images_response() {
+        let json = r#"
+        {
+            "images": [
+                {"id": "70a599e0-31e7-49b7-b260-868f441e862b", "name": "cirros", "status": "active"},
+                {"id": "155d900f-4e14-4e4c-a73d-069cbf4541e6", "name": null}
+            ],
+            "next": "/v2/images?marker=155d900f-4e14-4e4c-a73d-069cbf4541e6",
+            "first": "/v2/images"
+        }
+        "#;
+        let page: ImagesResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(page.images[0].display_name(), "cirros");
+        assert_eq!(
+            page.images[1].display_name(),
+            "155d900f-4e14-4e4c-a73d-069cbf4541e6"
+        );
+        assert!(page.next.is_some());
+        assert_eq!(image_root("http://glance:9292/v2/"), "http://glance:9292");
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interface {
+    #[default]
+    Public,
+    Internal,
+    Admin,
+}
+
+impl Interface {
+    pub const ALL: [Interface; 3] = [Interface::Public, Interface::Internal, Interface::Admin];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "public" | "publicURL" => Some(Interface::Public),
+            "internal" | "internalURL" => Some(Interface::Internal),
+            "admin" | "adminURL" => Some(Interface::Admin),
+            _ => None,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Interface::Public => "public",
+            Interface::Internal => "internal",
+            Interface::Admin => "admin",
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::{Result, anyhow};
+use reqwest::{RequestBuilder, Url};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::openstack::category::Category;
+use crate::openstack::session::Session;
+
+// Nova API microversion, "2.79"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Microversion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl Microversion {
+    // highest version asked for unless the profile says otherwise
+    pub const DEFAULT_MAX: Microversion = Microversion::new(2, 96);
+    // server fields that only exist from these versions on
+    pub const DESCRIPTION: Microversion = Microversion::new(2, 19);
+    pub const TAGS: Microversion = Microversion::new(2, 26);
+    // POST /servers needs `networks` from here on, "auto" for the default network
+    pub const AUTO_NETWORKS: Microversion = Microversion::new(2, 37);
+
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl fmt::Display for Microversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for Microversion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (major, minor) = s
+            .trim()
+            .split_once('.')
+            .ok_or_else(|| anyhow!("{} is not a microversion like 2.79", s))?;
+        Ok(Self {
+            major: major.parse()?,
+            minor: minor.parse()?,
+        })
+    }
+}
+
+// Adds the headers that pick the compute microversion, if one was negotiated
+pub fn compute_headers(request: RequestBuilder, version: Option<Microversion>) -> RequestBuilder {
+    match version {
+        Some(version) => request
+            .header("OpenStack-API-Version", format!("compute {}", version))
+            // understood by releases before the generic header
+            .header("X-OpenStack-Nova-API-Version", version.to_string()),
+        None => request,
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct VersionsResponse {
+    versions: Vec<Version>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Version {
+    id: String,
+    // empty for v2.0, which has no microversions
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    min_version: String,
+}
+
+// "http://nova:8774/v2.1/abc" -> "http://nova:8774/"
+fn compute_root(url: &str) -> Result<Url> {
+    let mut url = Url::parse(url.trim())?;
+    let segments: Vec<String> = url
+        .path_segments()
+        .map(|s| s.map(str::to_string).collect())
+        .unwrap_or_default();
+    let keep = segments
+        .iter()
+        .position(|s| s.starts_with("v2"))
+        .unwrap_or(segments.len());
+    url.set_path(&format!("{}/", segments[..keep].join("/")));
+    Ok(url)
+}
+
+// Highest version both Nova and the profile accept, None for plain 2.1
+fn negotiate(versions: &VersionsResponse, max: Microversion) -> Option<Microversion> {
+    let v21 = versions.versions.iter().find(|v| v.id == "v2.1")?;
+    let server_max: Microversion = v21.version.parse().ok()?;
+    let server_min: Microversion = v21.min_version.parse().ok()?;
+    let version = server_max.min(max);
+    (version >= server_min).then_some(version)
+}
+
+// マイクロバージョン取得 (GET compute root)
+pub async fn discover(
+    url: &str,
+    session: &Session,
+    max: Microversion,
+) -> Result<Option<Microversion>> {
+    let root = compute_root(url)?;
+    // the root answers 300 Multiple Choices on some deployments
+    let resp = session
+        .send(Category::Compute, |client| client.get(root.clone()))
+        .await?;
+    let versions = resp.json::<VersionsResponse>().await?;
+    Ok(negotiate(&versions, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_microversion_order() {
+        let v: Microversion = "2.9".parse().unwrap();
+        assert!(v < "2.10".parse().unwrap());
+        assert_eq!(v.to_string(), "2.9");
+        assert!("latest".parse::<Microversion>().is_err());
+    }
+
+    #[test]
+    fn test_compute_root() {
+        assert_eq!(
+            compute_root("http://nova:8774/v2.1/abc").unwrap().as_str(),
+            "http://nova:8774/"
+        );
+        assert_eq!(
+            compute_root("https://cloud.example.com/compute/v2.1")
+                .unwrap()
+                .as_str(),
+            "https://cloud.example.com/compute/"
+        );
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let json = r#"
+        {
+            "versions": [
+                {"id": "v2.0", "status": "SUPPORTED", "version": "", "min_version": ""},
+                {"id": "v2.1", "status": "CURRENT", "version": "2.79", "min_version": "2.1"}
+            ]
+        }"#;
+        let versions: VersionsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            negotiate(&versions, Microversion::DEFAULT_MAX),
+            Some(Microversion::new(2, 79))
+        );
+        assert_eq!(
+            negotiate(&versions, Microversion::new(2, 47)),
+            Some(Microversion::new(2, 47))
+        );
+        assert_eq!(negotiate(&versions, Microversion::new(2, 0)), None);
+    }
+}
@@ -1,3 +1,17 @@
-pub mod token;
+pub mod action;
+pub mod auth_method;
+pub mod category;
+pub mod column;
+pub mod create;
+pub mod error;
+pub mod filter;
+pub mod image;
+pub mod interface;
+pub mod microversion;
+pub mod network;
+pub mod neutron;
+pub mod retry;
+pub mod scope;
 pub mod server;
-mod category;
+pub mod session;
+pub mod token;
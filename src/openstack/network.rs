@@ -0,0 +1,177 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::openstack::category::Category;
+use crate::openstack::session::Session;
+
+// resources asked for per request, Neutron may cap it
+const PAGE_LIMIT: usize = 200;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Network {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SecurityGroup {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct NetworksResponse {
+    networks: Vec<Network>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SecurityGroupsResponse {
+    security_groups: Vec<SecurityGroup>,
+}
+
+// Any Neutron resource as returned, the browser shows every field
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Resource(pub Map<String, Value>);
+
+impl Resource {
+    pub fn id(&self) -> String {
+        self.text("id")
+    }
+
+    // A field as shown in a table cell, nested values as compact JSON
+    pub fn text(&self, key: &str) -> String {
+        self.0.get(key).map(value_text).unwrap_or_default()
+    }
+
+    // "ip_address" of every entry of a fixed_ips style list
+    pub fn ips(&self, key: &str) -> String {
+        self.0
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|ips| {
+                ips.iter()
+                    .filter_map(|ip| ip.get("ip_address")?.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => if *b { "yes" } else { "no" }.to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Link {
+    href: String,
+    rel: String,
+}
+
+// Neutron catalog entries usually have no version, some end in /v2.0
+fn network_root(url: &str) -> &str {
+    url.trim_end_matches('/').trim_end_matches("/v2.0")
+}
+
+// ネットワーク一覧取得
+pub async fn list_networks(url: &str, session: &Session) -> Result<Vec<Network>> {
+    let url = format!("{}/v2.0/networks", network_root(url));
+    let body: NetworksResponse = session.get_json(Category::Network, &url).await?;
+    Ok(body.networks)
+}
+
+// Neutron 一覧取得 (networks, subnets, ports, routers), following the next links
+pub async fn list_resources(
+    url: &str,
+    session: &Session,
+    collection: &str,
+    query: &[(&str, String)],
+) -> Result<Vec<Resource>> {
+    let mut first = reqwest::Url::parse(&format!("{}/v2.0/{}", network_root(url), collection))?;
+    first
+        .query_pairs_mut()
+        .extend_pairs(query)
+        .append_pair("limit", &PAGE_LIMIT.to_string());
+    let mut resources = Vec::new();
+    let mut next = Some(first.to_string());
+    while let Some(url) = next {
+        let body: Map<String, Value> = session.get_json(Category::Network, &url).await?;
+        let (page, link) = parse_page(body, collection)?;
+        // a last page that is full still links to an empty one
+        next = link.filter(|_| !page.is_empty());
+        resources.extend(page);
+    }
+    Ok(resources)
+}
+
+// {"ports": [...], "ports_links": [{"rel": "next", "href": ...}]}
+fn parse_page(
+    mut body: Map<String, Value>,
+    collection: &str,
+) -> Result<(Vec<Resource>, Option<String>)> {
+    let resources = match body.remove(collection) {
+        Some(list) => serde_json::from_value(list)?,
+        None => Vec::new(),
+    };
+    let links: Vec<Link> = match body.remove(&format!("{}_links", collection)) {
+        Some(links) => serde_json::from_value(links)?,
+        None => Vec::new(),
+    };
+    let next = links.into_iter().find(|l| l.rel == "next").map(|l| l.href);
+    Ok((resources, next))
+}
+
+// セキュリティグループ一覧取得
+pub async fn list_security_groups(url: &str, session: &Session) -> Result<Vec<SecurityGroup>> {
+    let url = format!("{}/v2.0/security-groups", network_root(url));
+    let body: SecurityGroupsResponse = session.get_json(Category::Network, &url).await?;
+    Ok(body.security_groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page() {
+        let json = r#"
+        {
+            "ports": [
+                {
+                    "id": "d80b1a3b-4fc1-49f3-952e-1e2ab7081d8b",
+                    "admin_state_up": true,
+                    "fixed_ips": [
+                        {"subnet_id": "a0304c3a-4f08-4c43-88af-d796509c97d2", "ip_address": "10.0.0.2"},
+                        {"subnet_id": "b1405d4b-5f19-5d54-99bf-e8a761ad08e3", "ip_address": "fd00::2"}
+                    ],
+                    "description": null
+                }
+            ],
+            "ports_links": [
+                {"href": "http://neutron:9696/v2.0/ports?limit=1&marker=d80b1a3b", "rel": "next"},
+                {"href": "http://neutron:9696/v2.0/ports?limit=1&page_reverse=True", "rel": "previous"}
+            ]
+        }"#;
+        let body: Map<String, Value> = serde_json::from_str(json).unwrap();
+        let (ports, next) = parse_page(body, "ports").unwrap();
+        assert_eq!(ports[0].id(), "d80b1a3b-4fc1-49f3-952e-1e2ab7081d8b");
+        assert_eq!(ports[0].ips("fixed_ips"), "10.0.0.2, fd00::2");
+        assert_eq!(ports[0].text("admin_state_up"), "yes");
+        assert_eq!(ports[0].text("description"), "");
+        assert_eq!(
+            next.as_deref(),
+            Some("http://neutron:9696/v2.0/ports?limit=1&marker=d80b1a3b")
+        );
+
+        let body: Map<String, Value> = serde_json::from_str(r#"{"ports": []}"#).unwrap();
+        assert_eq!(parse_page(body, "ports").unwrap(), (Vec::new(), None));
+    }
+}
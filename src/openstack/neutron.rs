@@ -0,0 +1,519 @@
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::style::{Style, Stylize};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Margin, Rect},
+    text::Line,
+    widgets::{
+        Block, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        StatefulWidget, Table, TableState, Tabs, Widget, Wrap,
+    },
+};
+use std::sync::{Arc, RwLock};
+
+use crate::openstack::error::Report;
+use crate::openstack::network::{self, Resource, value_text};
+use crate::openstack::retry::Retry;
+use crate::openstack::server::LoadingState;
+use crate::openstack::session::Session;
+
+// The Neutron resources the browser lists, one tab each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Networks,
+    Subnets,
+    Ports,
+    Routers,
+}
+
+impl Kind {
+    const ALL: [Kind; 4] = [Kind::Networks, Kind::Subnets, Kind::Ports, Kind::Routers];
+
+    fn title(self) -> &'static str {
+        match self {
+            Kind::Networks => "Networks",
+            Kind::Subnets => "Subnets",
+            Kind::Ports => "Ports",
+            Kind::Routers => "Routers",
+        }
+    }
+
+    // path under /v2.0 and key of the list in the response
+    fn collection(self) -> &'static str {
+        match self {
+            Kind::Networks => "networks",
+            Kind::Subnets => "subnets",
+            Kind::Ports => "ports",
+            Kind::Routers => "routers",
+        }
+    }
+
+    fn columns(self) -> &'static [(&'static str, u16)] {
+        match self {
+            Kind::Networks => &[
+                ("Name", 20),
+                ("ID", 36),
+                ("Status", 8),
+                ("Subnets", 7),
+                ("Shared", 6),
+                ("External", 8),
+                ("MTU", 5),
+            ],
+            Kind::Subnets => &[
+                ("Name", 20),
+                ("ID", 36),
+                ("Network ID", 36),
+                ("CIDR", 18),
+                ("IP", 2),
+                ("Gateway", 15),
+                ("DHCP", 4),
+            ],
+            Kind::Ports => &[
+                ("Name", 16),
+                ("ID", 36),
+                ("Status", 6),
+                ("Fixed IPs", 24),
+                ("MAC Address", 17),
+                ("Device Owner", 20),
+                ("Device ID", 36),
+            ],
+            Kind::Routers => &[
+                ("Name", 20),
+                ("ID", 36),
+                ("Status", 8),
+                ("External Network", 36),
+                ("External IPs", 24),
+                ("Admin Up", 8),
+            ],
+        }
+    }
+
+    fn cells(self, resource: &Resource) -> Vec<String> {
+        let text = |key| resource.text(key);
+        match self {
+            Kind::Networks => vec![
+                text("name"),
+                text("id"),
+                text("status"),
+                resource
+                    .0
+                    .get("subnets")
+                    .and_then(|s| s.as_array())
+                    .map_or(0, Vec::len)
+                    .to_string(),
+                text("shared"),
+                text("router:external"),
+                text("mtu"),
+            ],
+            Kind::Subnets => vec![
+                text("name"),
+                text("id"),
+                text("network_id"),
+                text("cidr"),
+                text("ip_version"),
+                text("gateway_ip"),
+                text("enable_dhcp"),
+            ],
+            Kind::Ports => vec![
+                text("name"),
+                text("id"),
+                text("status"),
+                resource.ips("fixed_ips"),
+                text("mac_address"),
+                text("device_owner"),
+                text("device_id"),
+            ],
+            Kind::Routers => {
+                let gateway = Resource(
+                    resource
+                        .0
+                        .get("external_gateway_info")
+                        .and_then(|g| g.as_object())
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+                vec![
+                    text("name"),
+                    text("id"),
+                    text("status"),
+                    gateway.text("network_id"),
+                    gateway.ips("external_fixed_ips"),
+                    text("admin_state_up"),
+                ]
+            }
+        }
+    }
+}
+
+pub enum BrowserResult {
+    Open,
+    Close,
+}
+
+// Tabs with the networks, subnets, ports and routers of the project
+pub struct NeutronBrowser {
+    url: String,
+    session: Session,
+    kind: Kind,
+    // one list per tab, in Kind::ALL order, loaded when first shown
+    lists: [ResourceListWidget; 4],
+    // only the ports of this server, (id, name), when opened from the server view
+    device: Option<(String, String)>,
+    // detail pane of the selected resource, open while Some (the scroll offset)
+    detail: Option<u16>,
+}
+
+impl NeutronBrowser {
+    pub fn open(url: String, session: Session, kind: Kind) -> Self {
+        let browser = Self {
+            url,
+            session,
+            kind,
+            lists: Default::default(),
+            device: None,
+            detail: None,
+        };
+        browser.load(kind);
+        browser
+    }
+
+    // The ports of one server, where its addresses come from
+    pub fn ports_of(url: String, session: Session, id: String, name: String) -> Self {
+        let browser = Self {
+            url,
+            session,
+            kind: Kind::Ports,
+            lists: Default::default(),
+            device: Some((id, name)),
+            detail: None,
+        };
+        browser.load(Kind::Ports);
+        browser
+    }
+
+    fn list(&self, kind: Kind) -> &ResourceListWidget {
+        &self.lists[Kind::ALL.iter().position(|k| *k == kind).unwrap_or(0)]
+    }
+
+    fn load(&self, kind: Kind) {
+        let query = match (&self.device, kind) {
+            (Some((id, _)), Kind::Ports) => vec![("device_id", id.clone())],
+            _ => Vec::new(),
+        };
+        self.list(kind)
+            .load(self.url.clone(), self.session.clone(), kind, query);
+    }
+
+    fn switch(&mut self, step: isize) {
+        let index = Kind::ALL.iter().position(|k| *k == self.kind).unwrap_or(0);
+        let next = (index as isize + step).rem_euclid(Kind::ALL.len() as isize);
+        self.kind = Kind::ALL[next as usize];
+        self.detail = self.detail.map(|_| 0);
+        if self.list(self.kind).loading_state() == LoadingState::Idle {
+            self.load(self.kind);
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> BrowserResult {
+        let list = self.list(self.kind);
+        match key.code {
+            KeyCode::Esc if list.dismiss_error() => {}
+            KeyCode::Esc if self.detail.is_some() => self.detail = None,
+            KeyCode::Esc => return BrowserResult::Close,
+            KeyCode::Tab => self.switch(1),
+            KeyCode::BackTab => self.switch(-1),
+            KeyCode::Enter if self.detail.is_some() => self.detail = None,
+            KeyCode::Enter => self.detail = Some(0),
+            KeyCode::Char('J') => self.detail = self.detail.map(|s| s.saturating_add(1)),
+            KeyCode::Char('K') => self.detail = self.detail.map(|s| s.saturating_sub(1)),
+            KeyCode::Char('r') => self.load(self.kind),
+            _ => {
+                list.handle_key(key);
+                self.detail = self.detail.map(|_| 0);
+            }
+        }
+        BrowserResult::Open
+    }
+
+    pub fn handle_mouse(&self, mouse: MouseEvent) {
+        self.list(self.kind).handle_mouse(mouse);
+    }
+
+    fn title(&self) -> String {
+        match (&self.device, self.kind) {
+            (Some((_, name)), Kind::Ports) => format!("Ports of {}", name),
+            _ => self.kind.title().to_string(),
+        }
+    }
+}
+
+impl Widget for &NeutronBrowser {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [tabs_area, body_area] = area.layout(&layout);
+        let selected = Kind::ALL.iter().position(|k| *k == self.kind);
+        Tabs::new(Kind::ALL.iter().map(|k| k.title()))
+            .select(selected)
+            .highlight_style(Style::new().bold().reversed())
+            .render(tabs_area, buf);
+
+        let list = self.list(self.kind);
+        list.state.write().unwrap().retrying = self.session.retrying();
+        let title = self.title();
+        match self.detail {
+            Some(scroll) => {
+                let layout = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]);
+                let [list_area, detail_area] = body_area.layout(&layout);
+                list.render(&title, self.kind, list_area, buf);
+                list.render_detail(scroll, detail_area, buf);
+            }
+            None => list.render(&title, self.kind, body_area, buf),
+        }
+        if let Some(report) = list.state.read().unwrap().error.as_ref() {
+            report.render(body_area, buf);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ResourceListWidget {
+    state: Arc<RwLock<ResourceListState>>,
+}
+
+#[derive(Debug, Default)]
+struct ResourceListState {
+    resources: Vec<Resource>,
+    loading_state: LoadingState,
+    table_state: TableState,
+    // where the table was last drawn, for the page size
+    area: Rect,
+    // bumped on every load so an older response is dropped
+    generation: usize,
+    error: Option<Report>,
+    // request waiting to be sent again, shown in place of the loading state
+    retrying: Option<Retry>,
+}
+
+impl ResourceListState {
+    fn page_size(&self) -> usize {
+        usize::from(self.area.height.saturating_sub(3)).max(1)
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.resources.is_empty() {
+            self.table_state.select(None);
+        } else {
+            self.table_state
+                .select(Some(index.min(self.resources.len() - 1)));
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let current = self.table_state.selected().unwrap_or(0);
+        self.select(current.saturating_add_signed(delta));
+    }
+
+    // Keep the same resource selected after a reload, by ID
+    fn replace(&mut self, resources: Vec<Resource>) {
+        let selected = self
+            .table_state
+            .selected()
+            .and_then(|i| self.resources.get(i))
+            .map(Resource::id);
+        self.resources = resources;
+        let index = selected
+            .and_then(|id| self.resources.iter().position(|r| r.id() == id))
+            .unwrap_or(0);
+        self.select(index);
+    }
+
+    fn selected(&self) -> Option<&Resource> {
+        self.resources.get(self.table_state.selected()?)
+    }
+}
+
+impl ResourceListWidget {
+    const SCROLL_STEP: isize = 3;
+
+    fn load(&self, url: String, session: Session, kind: Kind, query: Vec<(&'static str, String)>) {
+        let generation = {
+            let mut state = self.state.write().unwrap();
+            state.loading_state = LoadingState::Loading;
+            state.generation += 1;
+            state.generation
+        };
+        let this = self.clone();
+        tokio::spawn(async move {
+            let result = network::list_resources(&url, &session, kind.collection(), &query).await;
+            let mut state = this.state.write().unwrap();
+            if state.generation != generation {
+                return;
+            }
+            match result {
+                Ok(resources) => {
+                    state.loading_state = LoadingState::Loaded;
+                    state.replace(resources);
+                }
+                Err(err) => {
+                    state.loading_state = LoadingState::Error;
+                    state.error = Some(Report::new(format!("Loading {}", kind.collection()), &err));
+                }
+            }
+        });
+    }
+
+    fn loading_state(&self) -> LoadingState {
+        self.state.read().unwrap().loading_state.clone()
+    }
+
+    fn dismiss_error(&self) -> bool {
+        self.state.write().unwrap().error.take().is_some()
+    }
+
+    fn handle_key(&self, key: KeyEvent) {
+        let mut state = self.state.write().unwrap();
+        let page = state.page_size() as isize;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => state.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => state.move_selection(-1),
+            KeyCode::PageDown => state.move_selection(page),
+            KeyCode::PageUp => state.move_selection(-page),
+            KeyCode::Char('g') | KeyCode::Home => state.select(0),
+            KeyCode::Char('G') | KeyCode::End => state.select(usize::MAX),
+            _ => {}
+        }
+    }
+
+    fn handle_mouse(&self, mouse: MouseEvent) {
+        let mut state = self.state.write().unwrap();
+        match mouse.kind {
+            MouseEventKind::ScrollDown => state.move_selection(Self::SCROLL_STEP),
+            MouseEventKind::ScrollUp => state.move_selection(-Self::SCROLL_STEP),
+            _ => {}
+        }
+    }
+
+    fn render(&self, title: &str, kind: Kind, area: Rect, buf: &mut Buffer) {
+        let mut guard = self.state.write().unwrap();
+        // reborrow so rows and table_state can be borrowed separately
+        let state = &mut *guard;
+        state.area = area;
+
+        let loading_state = match &state.retrying {
+            Some(retry) => Line::from(format!("Retrying: {}", retry)).yellow(),
+            None => Line::from(format!("{:?}", state.loading_state)),
+        };
+        let block = Block::bordered()
+            .title(format!("{} ({})", title, state.resources.len()))
+            .title(loading_state.right_aligned())
+            .title_bottom(
+                "Tab/Shift+Tab switch, j/k/PgUp/PgDn/g/G to scroll, Enter details, r refresh, Esc back to servers",
+            );
+
+        let columns = kind.columns();
+        let header = Row::new(columns.iter().map(|(name, _)| *name)).bold();
+        let widths = columns.iter().map(|(_, width)| Constraint::Length(*width));
+        let rows: Vec<Row> = state
+            .resources
+            .iter()
+            .map(|r| Row::new(kind.cells(r)))
+            .collect();
+        let count = rows.len();
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(block)
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+            .row_highlight_style(Style::new().on_blue());
+        StatefulWidget::render(table, area, buf, &mut state.table_state);
+
+        let mut scrollbar_state =
+            ScrollbarState::new(count).position(state.table_state.selected().unwrap_or(0));
+        StatefulWidget::render(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            area.inner(Margin::new(0, 1)),
+            buf,
+            &mut scrollbar_state,
+        );
+    }
+
+    // Every field of the selected resource, in key order
+    fn render_detail(&self, scroll: u16, area: Rect, buf: &mut Buffer) {
+        let state = self.state.read().unwrap();
+        let selected = state.selected();
+        let title = selected.map(|r| r.text("name")).unwrap_or_default();
+        let lines: Vec<Line> = selected
+            .map(|r| {
+                r.0.iter()
+                    .map(|(key, value)| {
+                        Line::from(vec![format!("{}: ", key).bold(), value_text(value).into()])
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_bottom("J/K to scroll, Enter to close"),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0))
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells() {
+        let router: Resource = serde_json::from_str(
+            r#"{
+                "id": "f8a44de0-fc8e-45df-93c7-f79bf3b01c95",
+                "name": "router1",
+                "status": "ACTIVE",
+                "admin_state_up": true,
+                "external_gateway_info": {
+                    "network_id": "ae34051f-aa6c-4c75-abf5-50dc9ac99ef3",
+                    "external_fixed_ips": [{"ip_address": "172.24.4.6", "subnet_id": "b930d7f6"}]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Kind::Routers.cells(&router),
+            [
+                "router1",
+                "f8a44de0-fc8e-45df-93c7-f79bf3b01c95",
+                "ACTIVE",
+                "ae34051f-aa6c-4c75-abf5-50dc9ac99ef3",
+                "172.24.4.6",
+                "yes",
+            ]
+        );
+
+        let network: Resource =
+            serde_json::from_str(r#"{"name": "private", "subnets": ["a", "b"], "mtu": 1450}"#)
+                .unwrap();
+        let cells = Kind::Networks.cells(&network);
+        assert_eq!(cells[3], "2");
+        assert_eq!(cells[6], "1450");
+        for kind in Kind::ALL {
+            assert_eq!(kind.cells(&Resource::default()).len(), kind.columns().len());
+        }
+    }
+
+    #[test]
+    fn test_replace_keeps_selection() {
+        let resource =
+            |id: &str| Resource(serde_json::from_str(&format!(r#"{{"id": "{}"}}"#, id)).unwrap());
+        let mut state = ResourceListState::default();
+        state.replace(vec![resource("a"), resource("b"), resource("c")]);
+        assert_eq!(state.selected().map(Resource::id).as_deref(), Some("a"));
+        state.select(2);
+        state.replace(vec![resource("c"), resource("d")]);
+        assert_eq!(state.selected().map(Resource::id).as_deref(), Some("c"));
+    }
+}
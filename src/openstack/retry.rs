@@ -0,0 +1,310 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::openstack::category::Category;
+use crate::openstack::error::Error;
+use crate::profile::Profile;
+
+// How often and how long to wait before giving up on a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    // retries after the first attempt, 0 turns retrying off
+    pub max_retries: u32,
+    // first backoff, doubled on every retry
+    pub base_delay: Duration,
+    // longest wait, a longer Retry-After gives up instead
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl From<&Profile> for RetryPolicy {
+    fn from(profile: &Profile) -> Self {
+        Self {
+            max_retries: profile.max_retries,
+            base_delay: Duration::from_millis(profile.retry_delay),
+            ..Self::default()
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Exponential backoff with equal jitter: half of the doubled delay, plus up to
+    // the other half at random so clients that failed together don't retry together
+    fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        delay / 2 + jitter(delay / 2)
+    }
+}
+
+fn jitter(max: Duration) -> Duration {
+    // RandomState is seeded randomly, good enough without pulling in rand
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+// GET, PUT and DELETE can be sent again without doing anything twice
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+// Only requests that can be repeated safely are retried. A rate limiter that
+// sends Retry-After has not run the request, so a 429 with it is retried too
+fn should_retry(idempotent: bool, status: StatusCode, retry_after: bool) -> bool {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => idempotent || retry_after,
+        StatusCode::CONFLICT
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => idempotent,
+        _ => false,
+    }
+}
+
+// Retry-After is either seconds or an HTTP date
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).to_std().unwrap_or_default())
+}
+
+// A request waiting to be sent again, for the loading state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retry {
+    pub service: Category,
+    // "503 Service Unavailable", "timed out"
+    pub reason: String,
+    pub retry: u32,
+    pub max_retries: u32,
+    pub at: Instant,
+    // other requests waiting to be sent again at the same time
+    pub others: usize,
+}
+
+impl fmt::Display for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wait = self.at.saturating_duration_since(Instant::now());
+        write!(
+            f,
+            "{} {}, retry {}/{} in {:.1}s",
+            self.service.as_str(),
+            self.reason,
+            self.retry,
+            self.max_retries,
+            wait.as_secs_f32()
+        )?;
+        if self.others > 0 {
+            write!(f, " (+{} more)", self.others)?;
+        }
+        Ok(())
+    }
+}
+
+// The retries being waited for, one per request, shared with the UI
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    retries: Arc<RwLock<BTreeMap<u64, Retry>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Progress {
+    // The retry of the request that started first, with the count of the others
+    pub fn current(&self) -> Option<Retry> {
+        let retries = self.retries.read().unwrap();
+        let mut retry = retries.values().next()?.clone();
+        retry.others = retries.len() - 1;
+        Some(retry)
+    }
+
+    fn request(&self) -> ProgressEntry<'_> {
+        ProgressEntry {
+            progress: self,
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+// The slot of one request in Progress, cleared when the request is done
+// (or dropped half way)
+struct ProgressEntry<'a> {
+    progress: &'a Progress,
+    id: u64,
+}
+
+impl ProgressEntry<'_> {
+    fn set(&self, retry: Retry) {
+        self.progress
+            .retries
+            .write()
+            .unwrap()
+            .insert(self.id, retry);
+    }
+}
+
+impl Drop for ProgressEntry<'_> {
+    fn drop(&mut self) {
+        self.progress.retries.write().unwrap().remove(&self.id);
+    }
+}
+
+// Send the request built by `request`, again and again as the policy allows.
+// `idempotent` marks a POST that is safe to repeat. Failed connections become
+// Error, statuses are left to the caller
+pub async fn send<F>(
+    policy: &RetryPolicy,
+    service: Category,
+    idempotent: bool,
+    request: F,
+    progress: Option<&Progress>,
+) -> Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let entry = progress.map(Progress::request);
+    let mut attempt = 0;
+    let result = loop {
+        let (client, request) = request().build_split();
+        let request = request?;
+        let idempotent = idempotent || is_idempotent(request.method());
+        let result = client.execute(request).await;
+        attempt += 1;
+        let (delay, reason) = match &result {
+            _ if attempt > policy.max_retries => break result,
+            Ok(resp) => {
+                let after = retry_after(resp.headers(), Utc::now());
+                if !should_retry(idempotent, resp.status(), after.is_some()) {
+                    break result;
+                }
+                match after {
+                    Some(delay) if delay > policy.max_delay => break result,
+                    Some(delay) => (delay, resp.status().to_string()),
+                    None => (policy.backoff(attempt), resp.status().to_string()),
+                }
+            }
+            Err(e) if idempotent && (e.is_timeout() || e.is_connect()) => {
+                let reason = if e.is_timeout() {
+                    "timed out"
+                } else {
+                    "connection failed"
+                };
+                (policy.backoff(attempt), reason.to_string())
+            }
+            _ => break result,
+        };
+        if let Some(entry) = &entry {
+            entry.set(Retry {
+                service,
+                reason,
+                retry: attempt,
+                max_retries: policy.max_retries,
+                at: Instant::now() + delay,
+                others: 0,
+            });
+        }
+        tokio::time::sleep(delay).await;
+    };
+    // stop showing the wait once the retried request is answered
+    drop(entry);
+    result.map_err(|e| Error::from_reqwest(service, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        for retry in 1..=3 {
+            let full = Duration::from_millis(500) * 2u32.pow(retry - 1);
+            let delay = policy.backoff(retry);
+            assert!(delay >= full / 2 && delay <= full, "{:?}", delay);
+        }
+        // capped by max_delay
+        assert!(policy.backoff(20) <= policy.max_delay);
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let get = is_idempotent(&Method::GET);
+        let post = is_idempotent(&Method::POST);
+        assert!(should_retry(get, StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(!should_retry(post, StatusCode::SERVICE_UNAVAILABLE, true));
+        assert!(should_retry(get, StatusCode::CONFLICT, false));
+        assert!(!should_retry(post, StatusCode::CONFLICT, true));
+        // a rejected POST is only sent again when the limiter says when
+        assert!(should_retry(get, StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(!should_retry(post, StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(should_retry(post, StatusCode::TOO_MANY_REQUESTS, true));
+        assert!(!should_retry(get, StatusCode::NOT_FOUND, false));
+    }
+
+    #[test]
+    fn test_progress() {
+        let progress = Progress::default();
+        let retry = |service| Retry {
+            service,
+            reason: "503 Service Unavailable".to_string(),
+            retry: 1,
+            max_retries: 3,
+            at: Instant::now(),
+            others: 0,
+        };
+        let first = progress.request();
+        let second = progress.request();
+        first.set(retry(Category::Compute));
+        second.set(retry(Category::Network));
+        let current = progress.current().unwrap();
+        assert_eq!((current.service, current.others), (Category::Compute, 1));
+
+        // a request that is done leaves the one still waiting
+        drop(first);
+        let current = progress.current().unwrap();
+        assert_eq!((current.service, current.others), (Category::Network, 0));
+        drop(second);
+        assert_eq!(progress.current(), None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:30 GMT"),
+        );
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    #[default]
+    Project,
+    Domain,
+    Unscoped,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::Project, Scope::Domain, Scope::Unscoped];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Project => "project",
+            Scope::Domain => "domain",
+            Scope::Unscoped => "unscoped",
+        }
+    }
+}
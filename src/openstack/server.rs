@@ -1,35 +1,93 @@
+use crate::input::TextInput;
+use crate::openstack::action::{Action, ActionMenu, Confirm, MenuResult};
+use crate::openstack::category::Category;
+use crate::openstack::column::{Column, ColumnEditor, Columns, EditorResult, Sort};
+use crate::openstack::create::{self, CreateServerWizard, WizardResult};
+use crate::openstack::error::{self, Report};
+use crate::openstack::filter::{FilterPanel, Filters, PanelResult};
+use crate::openstack::microversion::{self, Microversion};
+use crate::openstack::neutron::{BrowserResult, Kind, NeutronBrowser};
+use crate::openstack::retry::Retry;
+use crate::openstack::session::Session;
+use crate::openstack::token;
+use crate::profile::{Profile, Store};
 use crate::state::AppState;
-use anyhow::{Result, anyhow};
-use crossterm::event::{Event, EventStream, KeyCode};
+use crate::totp::TotpPrompt;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::{
+    Event, EventStream, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::style::{Style, Stylize};
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
-    layout::{Constraint, Layout, Rect},
+    layout::{Constraint, Layout, Margin, Position, Rect},
     text::Line,
-    widgets::{Block, HighlightSpacing, Row, StatefulWidget, Table, TableState, Widget},
+    widgets::{
+        Block, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        StatefulWidget, Table, TableState, Widget, Wrap,
+    },
 };
-use reqwest::Client;
 use serde::Deserialize;
 use std::{
+    collections::BTreeMap,
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio_stream::StreamExt;
 
 pub struct Server {
     url: String,
+    session: Session,
+    profile: Profile,
     widget: ServerListWidget,
+    // column settings popup, open while Some
+    editor: Option<ColumnEditor>,
+    // detail pane of the selected server, open while Some
+    detail: Option<ServerDetailWidget>,
+    menu: Option<ActionMenu>,
+    confirm: Option<Confirm>,
+    // create-server wizard, shown in place of the table while Some
+    wizard: Option<CreateServerWizard>,
+    // networks, subnets, ports and routers, shown in place of the table while Some
+    neutron: Option<NeutronBrowser>,
+    filter_panel: Option<FilterPanel>,
+    // `/` search being typed, the title bar shows it while Some
+    search: Option<TextInput>,
+    // passcode asked for when MFA stops the token re-issue, shown over everything while Some
+    totp: Option<TotpPrompt>,
+    // the passcode was entered and is checked by the run loop
+    verifying: bool,
+    message: String,
     should_quit: bool,
+    next_state: AppState,
 }
 
 impl Server {
     const FRAMES_PER_SECOND: f32 = 60.0;
-    pub fn new(url: String) -> Self {
+
+    pub fn new(url: String, session: Session, profile: Profile) -> Self {
+        let widget = ServerListWidget::default();
+        widget.state.write().unwrap().columns = profile.columns.clone();
         Self {
-            url: url,
-            widget: ServerListWidget::default(),
+            url,
+            session,
+            profile,
+            widget,
+            editor: None,
+            detail: None,
+            menu: None,
+            confirm: None,
+            wizard: None,
+            neutron: None,
+            filter_panel: None,
+            search: None,
+            totp: None,
+            verifying: false,
+            message: String::new(),
             should_quit: false,
+            next_state: AppState::Quit,
         }
     }
 
@@ -37,37 +95,432 @@ impl Server {
         mut self,
         terminal: &mut DefaultTerminal,
     ) -> color_eyre::eyre::Result<AppState> {
-        self.widget.run(self.url.clone());
+        let max = self
+            .profile
+            .max_microversion
+            .parse()
+            .unwrap_or(Microversion::DEFAULT_MAX);
+        self.widget
+            .start(self.url.clone(), self.session.clone(), max);
         let period = Duration::from_secs_f32(1.0 / Self::FRAMES_PER_SECOND);
         let mut interval = tokio::time::interval(period);
+        // 0 turns the automatic refresh off, `r` still works
+        let auto_refresh = self.profile.refresh_interval > 0;
+        let every = Duration::from_secs(self.profile.refresh_interval.max(1));
+        let mut refresh = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
         let mut events = EventStream::new();
 
         while !self.should_quit {
             tokio::select! {
-                _ = interval.tick() => { terminal.draw(|frame| self.render(frame))?; },
-                Some(Ok(event)) = events.next() => self.handle_event(&event),
+                _ = interval.tick() => {
+                    self.check_receipt();
+                    terminal.draw(|frame| self.render(frame))?;
+                },
+                _ = refresh.tick(), if auto_refresh => {
+                    self.widget.refresh(self.url.clone(), self.session.clone());
+                },
+                Some(Ok(event)) = events.next() => {
+                    self.handle_event(&event);
+                    if self.verifying {
+                        self.verify_totp().await;
+                    }
+                },
             }
         }
 
-        Ok(AppState::Quit)
+        Ok(self.next_state)
     }
 
-    fn render(&self, frame: &mut Frame) {
+    fn render(&mut self, frame: &mut Frame) {
+        if let Some(prompt) = &self.totp {
+            prompt.render(frame);
+            return;
+        }
         let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
         let [title_area, body_area] = frame.area().layout(&layout);
 
-        let title = Line::from("Servers").centered().bold();
+        match &self.search {
+            Some(search) => {
+                let layout = Layout::horizontal([Constraint::Length(1), Constraint::Fill(1)]);
+                let [slash_area, input_area] = title_area.layout(&layout);
+                frame.render_widget(Line::from("/"), slash_area);
+                let width = input_area.width / 3;
+                let input_area = Rect {
+                    width,
+                    ..input_area
+                };
+                frame.render_widget(search, input_area);
+                let x = input_area.x + search.cursor_x(width);
+                frame.set_cursor_position((x, input_area.y));
+            }
+            None => frame.render_widget(Line::from(self.message.clone()).dim(), title_area),
+        }
+        let title = Line::from(self.title()).centered().bold();
         frame.render_widget(title, title_area);
-        frame.render_widget(&self.widget, body_area);
+        let version = self
+            .session
+            .compute_version()
+            .map_or_else(|| "2.1".to_string(), |v| v.to_string());
+        let expiry = format!(
+            "compute {}  {}",
+            version,
+            format_remaining(self.session.remaining())
+        );
+        let expiry = Line::from(expiry).right_aligned();
+        frame.render_widget(expiry, title_area);
+        self.widget.state.write().unwrap().retrying = self.session.retrying();
+        if let Some(wizard) = self.wizard.as_mut() {
+            if let Some(cursor) = wizard.cursor(body_area) {
+                frame.set_cursor_position(cursor);
+            }
+            frame.render_widget(wizard, body_area);
+            return;
+        }
+        if let Some(neutron) = &self.neutron {
+            frame.render_widget(neutron, body_area);
+            return;
+        }
+        match &self.detail {
+            Some(detail) => {
+                let layout = Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]);
+                let [list_area, detail_area] = body_area.layout(&layout);
+                frame.render_widget(&self.widget, list_area);
+                frame.render_widget(detail, detail_area);
+            }
+            None => frame.render_widget(&self.widget, body_area),
+        }
+        if let Some(report) = self.widget.error() {
+            frame.render_widget(&report, body_area);
+        }
+        if let Some(editor) = self.editor.as_mut() {
+            frame.render_widget(editor, body_area);
+        }
+        if let Some(menu) = self.menu.as_mut() {
+            frame.render_widget(menu, body_area);
+        }
+        if let Some(confirm) = &self.confirm {
+            frame.render_widget(confirm, body_area);
+        }
+        if let Some(panel) = &self.filter_panel {
+            frame.render_widget(panel, body_area);
+            frame.set_cursor_position(panel.cursor(body_area));
+        }
+    }
+
+    // "Servers [status=ACTIVE] /web" with the filters and search in effect
+    fn title(&self) -> String {
+        let mut title = "Servers".to_string();
+        let filters = self.widget.filters();
+        if !filters.is_empty() {
+            title.push_str(&format!(" [{}]", filters.summary()));
+        }
+        let search = self.widget.search();
+        if self.search.is_none() && !search.is_empty() {
+            title.push_str(&format!(" /{}", search));
+        }
+        title
     }
 
     fn handle_event(&mut self, event: &Event) {
-        if let Some(key) = event.as_key_press_event() {
-            match key.code {
-                KeyCode::Esc => self.should_quit = true,
-                _ => {}
+        if let Some(prompt) = self.totp.as_mut() {
+            match event {
+                Event::Paste(text) => prompt.paste(text),
+                _ => match prompt.handle_events(event.as_key_press_event()) {
+                    AppState::VerifyTotp => self.verifying = true,
+                    AppState::Totp => {}
+                    // no token without the passcode, so sign in again from the picker
+                    _ => {
+                        self.next_state = AppState::Profiles;
+                        self.should_quit = true;
+                    }
+                },
+            }
+            return;
+        }
+        if let Some(wizard) = self.wizard.as_mut() {
+            if let Event::Paste(text) = event {
+                wizard.paste(text);
+            }
+            let Some(key) = event.as_key_press_event() else {
+                return;
+            };
+            match wizard.handle_key(key) {
+                WizardResult::Open => {}
+                WizardResult::Cancel => self.wizard = None,
+                WizardResult::Submit { name, body } => {
+                    self.wizard = None;
+                    self.message = format!("Creating server {}", name);
+                    self.widget
+                        .create_server(self.url.clone(), self.session.clone(), name, body);
+                }
+            }
+            return;
+        }
+        if let Some(neutron) = self.neutron.as_mut() {
+            if let Event::Mouse(mouse) = event {
+                neutron.handle_mouse(*mouse);
+            }
+            let Some(key) = event.as_key_press_event() else {
+                return;
+            };
+            if let BrowserResult::Close = neutron.handle_key(key) {
+                self.neutron = None;
             }
+            return;
         }
+        if let Some(panel) = self.filter_panel.as_mut() {
+            if let Event::Paste(text) = event {
+                panel.paste(text);
+            }
+            let Some(key) = event.as_key_press_event() else {
+                return;
+            };
+            match panel.handle_key(key) {
+                PanelResult::Open => {}
+                PanelResult::Cancel => self.filter_panel = None,
+                PanelResult::Apply(filters) => {
+                    self.filter_panel = None;
+                    self.widget
+                        .set_filters(filters, self.url.clone(), self.session.clone());
+                }
+            }
+            return;
+        }
+        if let Some(search) = self.search.as_mut() {
+            match event {
+                Event::Paste(text) => search.paste(text),
+                _ => {
+                    let Some(key) = event.as_key_press_event() else {
+                        return;
+                    };
+                    match key.code {
+                        KeyCode::Enter => self.search = None,
+                        KeyCode::Esc => {
+                            self.search = None;
+                            self.widget.set_search("");
+                            return;
+                        }
+                        _ => {
+                            search.on_key_press(key);
+                        }
+                    }
+                }
+            }
+            if let Some(search) = &self.search {
+                self.widget.set_search(search.value());
+            }
+            self.follow_selection();
+            return;
+        }
+        let popup = self.editor.is_some() || self.menu.is_some() || self.confirm.is_some();
+        if let Event::Mouse(mouse) = event {
+            if !popup {
+                if let Some(column) = self.widget.handle_mouse(*mouse) {
+                    self.widget
+                        .sort_by(column, self.url.clone(), self.session.clone());
+                }
+                self.follow_selection();
+                self.widget
+                    .load_more(self.url.clone(), self.session.clone());
+            }
+            return;
+        }
+        let Some(key) = event.as_key_press_event() else {
+            return;
+        };
+        if let Some(editor) = self.editor.as_mut() {
+            match editor.handle_key(key) {
+                EditorResult::Editing => {}
+                EditorResult::Cancel => self.editor = None,
+                EditorResult::Apply(columns) => {
+                    self.editor = None;
+                    self.apply_columns(columns);
+                }
+            }
+            return;
+        }
+        if let Some(confirm) = self.confirm.take() {
+            if key.code == KeyCode::Char('y') {
+                self.run_action(confirm.id, confirm.action);
+            }
+            return;
+        }
+        if let Some(menu) = self.menu.as_mut() {
+            match menu.handle_key(key) {
+                MenuResult::Open => {}
+                MenuResult::Cancel => self.menu = None,
+                MenuResult::Choose(action) => {
+                    self.menu = None;
+                    if let Some((id, name)) = self.widget.selected_name() {
+                        if action.is_destructive() {
+                            self.confirm = Some(Confirm { action, id, name });
+                        } else {
+                            self.run_action(id, action);
+                        }
+                    }
+                }
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Esc if self.widget.dismiss_error() => {}
+            KeyCode::Esc if self.detail.is_some() => self.detail = None,
+            KeyCode::Esc if !self.widget.search().is_empty() => self.widget.set_search(""),
+            KeyCode::Esc => self.should_quit = true,
+            KeyCode::Enter if self.detail.is_some() => self.detail = None,
+            KeyCode::Enter => {
+                self.detail = Some(ServerDetailWidget::default());
+                self.follow_selection();
+            }
+            KeyCode::Char('J') => {
+                if let Some(detail) = &self.detail {
+                    detail.scroll(1);
+                }
+            }
+            KeyCode::Char('K') => {
+                if let Some(detail) = &self.detail {
+                    detail.scroll(-1);
+                }
+            }
+            // back to the profile picker to sign in elsewhere
+            KeyCode::Char('p') => {
+                self.next_state = AppState::Profiles;
+                self.should_quit = true;
+            }
+            KeyCode::Char('a') => {
+                if let Some((_, name)) = self.widget.selected_name() {
+                    self.menu = Some(ActionMenu::new(name));
+                }
+            }
+            KeyCode::Char('c') => {
+                let columns = self.widget.state.read().unwrap().columns.clone();
+                self.editor = Some(ColumnEditor::new(&columns));
+            }
+            KeyCode::Char('n') => self.open_wizard(),
+            KeyCode::Char('N') => self.open_neutron(None),
+            // the ports behind the addresses of the selected server
+            KeyCode::Char('o') => {
+                if let Some(server) = self.widget.selected_name() {
+                    self.open_neutron(Some(server));
+                }
+            }
+            KeyCode::Char('/') => {
+                let mut search = TextInput::new();
+                search.set_value(self.widget.search());
+                self.search = Some(search);
+            }
+            KeyCode::Char('s') => self
+                .widget
+                .cycle_sort(self.url.clone(), self.session.clone()),
+            KeyCode::Char('S') => self
+                .widget
+                .reverse_sort(self.url.clone(), self.session.clone()),
+            KeyCode::Char('f') => {
+                self.filter_panel = Some(FilterPanel::new(&self.widget.filters()))
+            }
+            KeyCode::Char('r') => self.widget.refresh(self.url.clone(), self.session.clone()),
+            _ => {
+                self.widget.handle_key(key);
+                self.follow_selection();
+                self.widget
+                    .load_more(self.url.clone(), self.session.clone());
+            }
+        }
+    }
+
+    // Ask for a passcode once the token could not be re-issued without one
+    fn check_receipt(&mut self) {
+        if self.totp.is_none()
+            && let Some(receipt) = self.session.receipt()
+        {
+            let mut prompt = TotpPrompt::new(self.profile.clone(), receipt);
+            prompt.message = "The token expired, Keystone asks for a new passcode".to_string();
+            self.totp = Some(prompt);
+        }
+    }
+
+    async fn verify_totp(&mut self) {
+        self.verifying = false;
+        let Some(prompt) = self.totp.as_mut() else {
+            return;
+        };
+        match self.session.verify_totp(prompt.passcode.value()).await {
+            Ok(()) => {
+                self.totp = None;
+                self.message = "Token re-issued".to_string();
+                // the requests that failed meanwhile are not retried, so reload
+                self.widget.dismiss_error();
+                self.widget.refresh(self.url.clone(), self.session.clone());
+            }
+            Err(e) => {
+                // the receipt stays valid for a while, so let the user retry
+                prompt.passcode.clear();
+                prompt.message = format!("Error verifying passcode: {}", e);
+            }
+        }
+    }
+
+    fn run_action(&mut self, id: String, action: Action) {
+        self.message = format!("{} requested", action.as_str());
+        self.widget
+            .run_action(self.url.clone(), self.session.clone(), id, action);
+    }
+
+    fn endpoint(&self, category: Category) -> Option<String> {
+        let endpoints = self.session.endpoints();
+        token::find_endpoint(
+            &endpoints,
+            category,
+            self.profile.interface,
+            &self.profile.region,
+        )
+        .map(|e| e.url.clone())
+    }
+
+    fn open_wizard(&mut self) {
+        let endpoint = |category| self.endpoint(category);
+        self.wizard = Some(CreateServerWizard::open(
+            self.url.clone(),
+            endpoint(Category::Image),
+            endpoint(Category::Network),
+            self.session.clone(),
+        ));
+    }
+
+    // `server` (id, name) opens the ports of that server instead of the networks
+    fn open_neutron(&mut self, server: Option<(String, String)>) {
+        let Some(url) = self.endpoint(Category::Network) else {
+            self.message = "No network endpoint in the catalog".to_string();
+            return;
+        };
+        let session = self.session.clone();
+        self.neutron = Some(match server {
+            Some((id, name)) => NeutronBrowser::ports_of(url, session, id, name),
+            None => NeutronBrowser::open(url, session, Kind::Networks),
+        });
+    }
+
+    // Show the newly selected server in the open detail pane
+    fn follow_selection(&mut self) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        let Some(id) = self.widget.selected_id() else {
+            return;
+        };
+        if detail.id() != id {
+            detail.load(self.url.clone(), self.session.clone(), id);
+        }
+    }
+
+    fn apply_columns(&mut self, columns: Columns) {
+        self.widget.state.write().unwrap().columns = columns.clone();
+        self.message = match Store::save_columns(&self.profile.name, &columns) {
+            Ok(()) => format!("Columns saved to profile {}", self.profile.name),
+            Err(e) => e.to_string(),
+        };
+        self.profile.columns = columns;
     }
 }
 
@@ -79,54 +532,696 @@ pub struct ServerListWidget {
 #[derive(Debug, Default)]
 struct ServerListState {
     servers: Vec<ServerState>,
+    columns: Columns,
     loading_state: LoadingState,
     table_state: TableState,
+    // where the table was last drawn, for mouse clicks and page size
+    area: Rect,
+    // when the last list request finished, None until the first one did
+    refreshed: Option<DateTime<Local>>,
+    // marker for the next page while Nova has more servers to return
+    next_marker: Option<String>,
+    // Nova side filters, sent with every list request
+    filters: Filters,
+    // bumped when the filters change so older responses are dropped
+    generation: usize,
+    // `/` search, applied to the loaded rows
+    search: String,
+    sort: Option<Sort>,
+    // last failure, shown until dismissed with Esc
+    error: Option<Report>,
+    // request waiting to be sent again, shown in place of the loading state
+    retrying: Option<Retry>,
 }
 
-#[derive(Debug, Clone)]
+impl ServerListState {
+    // Rows that fit between the borders and the header
+    fn page_size(&self) -> usize {
+        usize::from(self.area.height.saturating_sub(3)).max(1)
+    }
+
+    // Rows matching the search, the table and selection index into these
+    fn shown(&self) -> Vec<&ServerState> {
+        self.servers
+            .iter()
+            .filter(|s| s.matches(&self.search))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&ServerState> {
+        let index = self.table_state.selected()?;
+        self.shown().get(index).copied()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.selected().map(|s| s.id.clone())
+    }
+
+    fn select(&mut self, index: usize) {
+        let len = self.shown().len();
+        if len == 0 {
+            self.table_state.select(None);
+        } else {
+            self.table_state.select(Some(index.min(len - 1)));
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let current = self.table_state.selected().unwrap_or(0);
+        self.select(current.saturating_add_signed(delta));
+    }
+
+    // Keep the same server selected after the shown rows changed, by ID
+    fn keep_selection(&mut self, change: impl FnOnce(&mut Self)) {
+        let selected = self.selected_id();
+        let previous = self.table_state.selected().unwrap_or(0);
+        change(self);
+        let index = selected
+            .and_then(|id| self.shown().iter().position(|s| s.id == id))
+            .unwrap_or(previous);
+        self.select(index);
+    }
+
+    fn replace_servers(&mut self, servers: Vec<ServerState>) {
+        self.keep_selection(|state| {
+            state.servers = servers;
+            state.sort_rows();
+        });
+    }
+
+    // Sort the loaded rows by the sort column, stable so equal cells keep Nova's order
+    fn sort_rows(&mut self) {
+        let Some(sort) = self.sort else {
+            return;
+        };
+        self.servers.sort_by(|a, b| {
+            let order = a.cell(sort.column).cmp(b.cell(sort.column));
+            if sort.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    fn set_sort(&mut self, sort: Sort) {
+        self.keep_selection(|state| {
+            state.sort = Some(sort);
+            state.sort_rows();
+        });
+    }
+
+    // Same column flips the direction, another one sorts ascending by it
+    fn next_sort(&self, column: Column) -> Sort {
+        match self.sort {
+            Some(sort) if sort.column == column => Sort {
+                descending: !sort.descending,
+                ..sort
+            },
+            _ => Sort {
+                column,
+                descending: false,
+            },
+        }
+    }
+
+    fn set_search(&mut self, search: &str) {
+        self.keep_selection(|state| state.search = search.to_string());
+    }
+
+    // Merge a fresh list by ID, marking what changed since the last one.
+    // Rows that disappeared stay for a moment, marked as removed.
+    // Nothing is marked on the first load, or the first after a reset
+    fn merge_servers(&mut self, servers: Vec<ServerState>, now: Instant) {
+        let first = self.refreshed.is_none();
+        let mut merged: Vec<ServerState> = servers
+            .into_iter()
+            .map(|mut server| {
+                match self.servers.iter().find(|s| s.id == server.id) {
+                    Some(old) => {
+                        // an action being followed keeps its progress text
+                        server.progress = old.progress.clone();
+                        server.change = if old.status != server.status {
+                            Some((Change::Status, now))
+                        } else {
+                            old.change.filter(|(c, _)| *c != Change::Removed)
+                        };
+                    }
+                    None if !first => server.change = Some((Change::Added, now)),
+                    None => {}
+                }
+                server
+            })
+            .collect();
+        for (index, old) in self.servers.iter().enumerate() {
+            if first || merged.iter().any(|s| s.id == old.id) {
+                continue;
+            }
+            let mut row = old.clone();
+            if !matches!(row.change, Some((Change::Removed, _))) {
+                row.change = Some((Change::Removed, now));
+            }
+            merged.insert(index.min(merged.len()), row);
+        }
+        self.replace_servers(merged);
+    }
+
+    // Add the next page below the rows already loaded
+    fn append_servers(&mut self, servers: Vec<ServerState>) {
+        self.keep_selection(|state| {
+            for server in servers {
+                if !state.servers.iter().any(|s| s.id == server.id) {
+                    state.servers.push(server);
+                }
+            }
+            state.sort_rows();
+        });
+    }
+
+    // Servers in the list, not counting rows only shown as removed
+    fn loaded(&self) -> usize {
+        self.servers
+            .iter()
+            .filter(|s| !matches!(s.change, Some((Change::Removed, _))))
+            .count()
+    }
+
+    // The selection is within a page of the last loaded row
+    fn near_end(&self) -> bool {
+        let selected = self.table_state.selected().unwrap_or(0);
+        selected + self.page_size() >= self.shown().len()
+    }
+
+    // Drop highlights older than HIGHLIGHT, and the removed rows with them
+    fn expire_changes(&mut self, now: Instant) {
+        let expired = |s: &ServerState| {
+            s.change
+                .is_some_and(|(_, at)| now.duration_since(at) >= ServerListWidget::HIGHLIGHT)
+        };
+        if !self.servers.iter().any(expired) {
+            return;
+        }
+        let servers = self
+            .servers
+            .iter()
+            .filter(|s| !(expired(s) && matches!(s.change, Some((Change::Removed, _)))))
+            .cloned()
+            .map(|mut s| {
+                if expired(&s) {
+                    s.change = None;
+                }
+                s
+            })
+            .collect();
+        self.replace_servers(servers);
+    }
+
+    fn update_server(&mut self, server: ServerState) {
+        self.keep_selection(|state| {
+            if let Some(row) = state.servers.iter_mut().find(|s| s.id == server.id) {
+                *row = server;
+            }
+            state.sort_rows();
+        });
+    }
+
+    fn set_progress(&mut self, id: &str, progress: Option<String>) {
+        if let Some(row) = self.servers.iter_mut().find(|s| s.id == id) {
+            row.progress = progress;
+        }
+    }
+
+    fn remove_server(&mut self, id: &str) {
+        let servers = self
+            .servers
+            .iter()
+            .filter(|s| s.id != id)
+            .cloned()
+            .collect();
+        self.replace_servers(servers);
+    }
+
+    // Select the row under a mouse click. A click on the header returns its column
+    fn click(&mut self, column: u16, row: u16) -> Option<Column> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+        // the first inner line is the header
+        if row == inner.y {
+            return self.header_at(column - inner.x);
+        }
+        let index = self.table_state.offset() + usize::from(row - inner.y - 1);
+        if index < self.shown().len() {
+            self.select(index);
+        }
+        None
+    }
+
+    // Column under `x` cells from the inner left edge, past the highlight symbol
+    fn header_at(&self, x: u16) -> Option<Column> {
+        let mut left = ServerListWidget::HIGHLIGHT_SYMBOL.len() as u16;
+        for setting in self.columns.visible() {
+            if (left..left + setting.width).contains(&x) {
+                return Some(setting.column);
+            }
+            // one cell of spacing between columns
+            left += setting.width + 1;
+        }
+        None
+    }
+}
+
+// One server as shown in the table, every value already formatted
+#[derive(Debug, Clone, Default)]
 struct ServerState {
     id: String,
     name: String,
+    status: String,
+    task_state: String,
+    vm_state: String,
+    power_state: String,
+    flavor: String,
+    image: String,
+    ips: String,
+    availability_zone: String,
+    host: String,
+    key_name: String,
+    created: String,
+    updated: String,
+    // action in progress, shown in place of the task state
+    progress: Option<String>,
+    // highlighted for a moment after a refresh
+    change: Option<(Change, Instant)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Added,
+    Status,
+    Removed,
+}
+
+impl ServerState {
+    fn cell(&self, column: Column) -> &str {
+        match column {
+            Column::Id => &self.id,
+            Column::Name => &self.name,
+            Column::Status => &self.status,
+            Column::TaskState => self.progress.as_deref().unwrap_or(&self.task_state),
+            Column::VmState => &self.vm_state,
+            Column::PowerState => &self.power_state,
+            Column::Flavor => &self.flavor,
+            Column::Image => &self.image,
+            Column::Ips => &self.ips,
+            Column::AvailabilityZone => &self.availability_zone,
+            Column::Host => &self.host,
+            Column::KeyName => &self.key_name,
+            Column::Created => &self.created,
+            Column::Updated => &self.updated,
+        }
+    }
+
+    // Every word of the search fuzzy-matches the name, ID, IPs or status
+    fn matches(&self, search: &str) -> bool {
+        search.split_whitespace().all(|word| {
+            [&self.name, &self.id, &self.ips, &self.status]
+                .iter()
+                .any(|field| fuzzy_match(word, field))
+        })
+    }
+
+    fn row(&self, columns: &[Column]) -> Row<'_> {
+        let row = Row::new(columns.iter().map(|c| self.cell(*c)));
+        if self.progress.is_some() {
+            return row.yellow();
+        }
+        match self.change {
+            Some((Change::Added, _)) => row.green(),
+            Some((Change::Status, _)) => row.cyan(),
+            Some((Change::Removed, _)) => row.red().crossed_out(),
+            None => row,
+        }
+    }
+}
+
+impl From<&Server_> for ServerState {
+    fn from(server: &Server_) -> Self {
+        Self {
+            id: server.id.clone(),
+            name: server.name.clone(),
+            status: server.status.clone(),
+            task_state: server.task_state.clone().unwrap_or_default(),
+            vm_state: server.vm_state.clone(),
+            power_state: power_state_name(server.power_state).to_string(),
+            flavor: server.flavor.name(),
+            image: server.image.name(),
+            ips: server.ips(),
+            availability_zone: server.availability_zone.clone().unwrap_or_default(),
+            host: server.host.clone().unwrap_or_default(),
+            key_name: server.key_name.clone().unwrap_or_default(),
+            created: format_time(server.created),
+            updated: format_time(server.updated),
+            progress: None,
+            change: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
-enum LoadingState {
+pub enum LoadingState {
     #[default]
     Idle,
     Loading,
     Loaded,
-    Error(String),
+    Error,
 }
 
 impl ServerListWidget {
-    fn run(&self, url: String) {
+    const SCROLL_STEP: isize = 3;
+    const HIGHLIGHT_SYMBOL: &str = ">>";
+    const POLL_INTERVAL: Duration = Duration::from_secs(2);
+    // give up following an action after about five minutes
+    const POLL_LIMIT: usize = 150;
+    // servers asked for per request, Nova may return fewer (osapi_max_limit)
+    const PAGE_LIMIT: usize = 200;
+    // how long added, changed and removed rows stay highlighted
+    const HIGHLIGHT: Duration = Duration::from_secs(3);
+
+    fn run(&self, url: String, session: Session) {
+        let this = self.clone();
+        tokio::spawn(this.fetch_servers(url, session));
+    }
+
+    // Negotiate the compute microversion, then load the first page with it
+    fn start(&self, url: String, session: Session, max: Microversion) {
         let this = self.clone();
-        tokio::spawn(this.fetch_servers(url));
+        tokio::spawn(async move {
+            this.set_loading_state(LoadingState::Loading);
+            // clouds that hide the version document still serve plain 2.1
+            let version = microversion::discover(&url, &session, max)
+                .await
+                .unwrap_or(None);
+            session.set_compute_version(version);
+            this.fetch_servers(url, session).await;
+        });
     }
 
-    async fn fetch_servers(self, url: String) {
-        self.set_loading_state(LoadingState::Loading);
-        match list_servers_detail(url).await {
-            Ok(resp) => self.on_load(&resp),
-            Err(err) => self.on_err(&err),
+    // Fetch the list again unless a fetch is still running
+    fn refresh(&self, url: String, session: Session) {
+        if self.state.read().unwrap().loading_state == LoadingState::Loading {
+            return;
         }
+        self.run(url, session);
     }
 
-    fn on_load(&self, servers: &ServersDetail) {
-        let servers = servers.servers.iter().map(|s| ServerState {
-            id: s.id.clone(),
-            name: s.name.clone(),
-        });
+    // Fetch as many pages as were loaded before, so a refresh doesn't shrink the list
+    async fn fetch_servers(self, url: String, session: Session) {
+        let (wanted, filters, sort, generation) = {
+            let mut state = self.state.write().unwrap();
+            state.loading_state = LoadingState::Loading;
+            let wanted = state.loaded().max(1);
+            (wanted, state.filters.clone(), state.sort, state.generation)
+        };
+        let mut servers = Vec::new();
+        let mut marker = None;
+        loop {
+            match list_servers_detail(&url, &session, &filters, sort, marker.as_deref()).await {
+                Ok(page) => {
+                    marker = page.next_marker();
+                    servers.extend(page.servers.iter().map(ServerState::from));
+                    if marker.is_none() || servers.len() >= wanted {
+                        break;
+                    }
+                }
+                Err(err) => return self.on_err("Loading servers", &err),
+            }
+        }
+        self.on_load(servers, marker, generation);
+    }
+
+    fn on_load(&self, servers: Vec<ServerState>, next_marker: Option<String>, generation: usize) {
         let mut state = self.state.write().unwrap();
+        // the filters changed while this was loading
+        if state.generation != generation {
+            return;
+        }
         state.loading_state = LoadingState::Loaded;
-        state.servers.extend(servers);
-        if !state.servers.is_empty() {
-            state.table_state.select(Some(0));
+        state.merge_servers(servers, Instant::now());
+        state.refreshed = Some(Local::now());
+        state.next_marker = next_marker;
+    }
+
+    // Fetch the next page in the background once the selection gets close to the end
+    fn load_more(&self, url: String, session: Session) {
+        let (marker, filters, sort, generation) = {
+            let mut state = self.state.write().unwrap();
+            if state.loading_state == LoadingState::Loading || !state.near_end() {
+                return;
+            }
+            let Some(marker) = state.next_marker.clone() else {
+                return;
+            };
+            state.loading_state = LoadingState::Loading;
+            (marker, state.filters.clone(), state.sort, state.generation)
+        };
+        let this = self.clone();
+        tokio::spawn(async move {
+            match list_servers_detail(&url, &session, &filters, sort, Some(&marker)).await {
+                Ok(page) => {
+                    let mut state = this.state.write().unwrap();
+                    if state.generation != generation {
+                        return;
+                    }
+                    state.loading_state = LoadingState::Loaded;
+                    state.next_marker = page.next_marker();
+                    state.append_servers(page.servers.iter().map(ServerState::from).collect());
+                }
+                Err(err) => this.on_err("Loading more servers", &err),
+            }
+        });
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.state.read().unwrap().selected_id()
+    }
+
+    fn selected_name(&self) -> Option<(String, String)> {
+        let state = self.state.read().unwrap();
+        state.selected().map(|s| (s.id.clone(), s.name.clone()))
+    }
+
+    fn set_search(&self, search: &str) {
+        self.state.write().unwrap().set_search(search);
+    }
+
+    fn search(&self) -> String {
+        self.state.read().unwrap().search.clone()
+    }
+
+    fn filters(&self) -> Filters {
+        self.state.read().unwrap().filters.clone()
+    }
+
+    // Start over from the first page with new Nova side filters
+    fn set_filters(&self, filters: Filters, url: String, session: Session) {
+        {
+            let mut state = self.state.write().unwrap();
+            state.filters = filters;
+            state.generation += 1;
+            state.refreshed = None;
+            state.next_marker = None;
+            state.replace_servers(Vec::new());
         }
+        self.run(url, session);
     }
 
-    fn on_err(&self, err: &anyhow::Error) {
-        self.set_loading_state(LoadingState::Error(err.to_string()));
+    // Sort by `column`, or flip the direction if already sorted by it
+    fn sort_by(&self, column: Column, url: String, session: Session) {
+        let sort = self.state.read().unwrap().next_sort(column);
+        self.apply_sort(sort, url, session);
+    }
+
+    // Sort by the next visible column
+    fn cycle_sort(&self, url: String, session: Session) {
+        let sort = {
+            let state = self.state.read().unwrap();
+            let visible = state.columns.visible();
+            let next = match state.sort {
+                Some(sort) => visible
+                    .iter()
+                    .position(|c| c.column == sort.column)
+                    .map_or(0, |i| i + 1),
+                None => 0,
+            };
+            let Some(setting) = visible.get(next % visible.len().max(1)) else {
+                return;
+            };
+            Sort {
+                column: setting.column,
+                descending: false,
+            }
+        };
+        self.apply_sort(sort, url, session);
+    }
+
+    fn reverse_sort(&self, url: String, session: Session) {
+        let sort = self.state.read().unwrap().sort;
+        if let Some(sort) = sort {
+            self.sort_by(sort.column, url, session);
+        }
+    }
+
+    // Sort what's loaded. While Nova has more pages, load the same number of rows
+    // again sorted by Nova, so later pages continue in the same order
+    fn apply_sort(&self, sort: Sort, url: String, session: Session) {
+        {
+            let mut state = self.state.write().unwrap();
+            state.set_sort(sort);
+            if state.next_marker.is_none() || sort.column.sort_key().is_none() {
+                return;
+            }
+            state.generation += 1;
+            state.refreshed = None;
+        }
+        self.run(url, session);
+    }
+
+    fn run_action(&self, url: String, session: Session, id: String, action: Action) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            this.state
+                .write()
+                .unwrap()
+                .set_progress(&id, Some(format!("{}...", action.as_str())));
+            match server_action(url.clone(), &session, &id, action).await {
+                Ok(()) => {
+                    let deleting = action == Action::Delete;
+                    this.follow(url, session, id, action.as_str(), deleting)
+                        .await
+                }
+                Err(err) => {
+                    this.state.write().unwrap().set_progress(&id, None);
+                    this.report(action.as_str(), &err);
+                }
+            }
+        });
+    }
+
+    // Add a placeholder row for the new server and follow its build
+    fn create_server(&self, url: String, session: Session, name: String, body: serde_json::Value) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            match create::create_server(&url, &session, &body).await {
+                Ok(created) => {
+                    {
+                        let mut state = this.state.write().unwrap();
+                        state.append_servers(vec![ServerState {
+                            id: created.id.clone(),
+                            name,
+                            status: "BUILD".to_string(),
+                            progress: Some("build...".to_string()),
+                            ..Default::default()
+                        }]);
+                        let shown = state.shown();
+                        if let Some(index) = shown.iter().position(|s| s.id == created.id) {
+                            state.select(index);
+                        }
+                    }
+                    this.follow(url, session, created.id, "build", false).await;
+                }
+                Err(err) => this.report(&format!("Creating {}", name), &err),
+            }
+        });
+    }
+
+    // Poll the server until its task state settles, updating the row as it goes
+    async fn follow(&self, url: String, session: Session, id: String, label: &str, deleting: bool) {
+        let started = std::time::Instant::now();
+        for _ in 0..Self::POLL_LIMIT {
+            tokio::time::sleep(Self::POLL_INTERVAL).await;
+            match get_server(&url, &session, &id).await {
+                Ok(server) => {
+                    let mut row = ServerState::from(&server);
+                    if !is_settled(&server) {
+                        let task = server.task_state.as_deref().unwrap_or(&server.status);
+                        row.progress = Some(format!(
+                            "{}: {} ({}s)",
+                            label,
+                            task,
+                            started.elapsed().as_secs()
+                        ));
+                    }
+                    let settled = row.progress.is_none();
+                    self.state.write().unwrap().update_server(row);
+                    if settled {
+                        return;
+                    }
+                }
+                Err(err)
+                    if deleting
+                        && err.downcast_ref::<error::Error>().is_some_and(|e| {
+                            e.status() == Some(reqwest::StatusCode::NOT_FOUND)
+                        }) =>
+                {
+                    self.state.write().unwrap().remove_server(&id);
+                    return;
+                }
+                Err(err) => {
+                    self.state.write().unwrap().set_progress(&id, None);
+                    self.report(&format!("Following {}", label), &err);
+                    return;
+                }
+            }
+        }
+        self.state.write().unwrap().set_progress(&id, None);
+    }
+
+    fn handle_key(&self, key: KeyEvent) {
+        let mut state = self.state.write().unwrap();
+        let page = state.page_size() as isize;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => state.move_selection(1),
+            KeyCode::Char('k') | KeyCode::Up => state.move_selection(-1),
+            KeyCode::PageDown => state.move_selection(page),
+            KeyCode::PageUp => state.move_selection(-page),
+            KeyCode::Char('g') | KeyCode::Home => state.select(0),
+            KeyCode::Char('G') | KeyCode::End => state.select(usize::MAX),
+            _ => {}
+        }
+    }
+
+    // Returns the column of a header click, for the caller to sort by
+    fn handle_mouse(&self, mouse: MouseEvent) -> Option<Column> {
+        let mut state = self.state.write().unwrap();
+        match mouse.kind {
+            MouseEventKind::ScrollDown => state.move_selection(Self::SCROLL_STEP),
+            MouseEventKind::ScrollUp => state.move_selection(-Self::SCROLL_STEP),
+            MouseEventKind::Down(MouseButton::Left) => return state.click(mouse.column, mouse.row),
+            _ => {}
+        }
+        None
+    }
+
+    // The list failed to load
+    fn on_err(&self, context: &str, err: &anyhow::Error) {
+        let mut state = self.state.write().unwrap();
+        state.loading_state = LoadingState::Error;
+        state.error = Some(Report::new(context, err));
+    }
+
+    // Something besides loading the list failed, the rows shown are still current
+    fn report(&self, context: &str, err: &anyhow::Error) {
+        self.state.write().unwrap().error = Some(Report::new(context, err));
+    }
+
+    fn error(&self) -> Option<Report> {
+        self.state.read().unwrap().error.clone()
+    }
+
+    // Returns whether there was an error to dismiss
+    fn dismiss_error(&self) -> bool {
+        self.state.write().unwrap().error.take().is_some()
     }
 
     fn set_loading_state(&self, state: LoadingState) {
@@ -136,64 +1231,892 @@ impl ServerListWidget {
 
 impl Widget for &ServerListWidget {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let mut state = self.state.write().unwrap();
+        let mut guard = self.state.write().unwrap();
+        // reborrow so rows and table_state can be borrowed separately
+        let state = &mut *guard;
+        state.area = area;
+        state.expire_changes(Instant::now());
 
-        let loading_state = Line::from(format!("{:?}", state.loading_state)).right_aligned();
+        let loaded = match state.next_marker {
+            Some(_) => format!("loaded {} of …", state.loaded()),
+            None => format!("{} servers", state.loaded()),
+        };
+        let title = match state.refreshed {
+            Some(time) => format!(
+                "Servers ({}, refreshed {})",
+                loaded,
+                time.format("%H:%M:%S")
+            ),
+            None => "Servers".to_string(),
+        };
+        let loading_state = match &state.retrying {
+            Some(retry) => Line::from(format!("Retrying: {}", retry)).yellow(),
+            None => Line::from(format!("{:?}", state.loading_state)),
+        };
         let block = Block::bordered()
-            .title("Servers")
-            .title(loading_state)
-            .title_bottom("j/k to scroll, Esc to quit");
+            .title(title)
+            .title(loading_state.right_aligned())
+            .title_bottom(
+                "j/k/PgUp/PgDn/g/G to scroll, Enter details, / search, f filters, s/S sort, a actions, n new, N networks, o ports, r refresh, c columns, p to switch profile, Esc to quit",
+            );
 
-        let rows = state.servers.iter();
-        let widths = [
-            Constraint::Length(36),
-            Constraint::Fill(1),
-            Constraint::Max(49),
-        ];
+        let visible = state.columns.visible();
+        let columns: Vec<Column> = visible.iter().map(|c| c.column).collect();
+        let header = Row::new(columns.iter().map(|c| Sort::label(state.sort, *c))).bold();
+        let widths = visible.iter().map(|c| Constraint::Length(c.width));
+        // field by field, state.shown() would borrow table_state too
+        let rows: Vec<Row> = state
+            .servers
+            .iter()
+            .filter(|s| s.matches(&state.search))
+            .map(|s| s.row(&columns))
+            .collect();
+        let count = rows.len();
         let table = Table::new(rows, widths)
+            .header(header)
             .block(block)
             .highlight_spacing(HighlightSpacing::Always)
-            .highlight_symbol(">>")
+            .highlight_symbol(ServerListWidget::HIGHLIGHT_SYMBOL)
             .row_highlight_style(Style::new().on_blue());
 
         StatefulWidget::render(table, area, buf, &mut state.table_state);
+
+        let mut scrollbar_state =
+            ScrollbarState::new(count).position(state.table_state.selected().unwrap_or(0));
+        let scrollbar_area = area.inner(Margin::new(0, 1));
+        StatefulWidget::render(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            scrollbar_area,
+            buf,
+            &mut scrollbar_state,
+        );
+    }
+}
+
+// Right hand pane with the full GET /servers/{id} payload of the selected server
+#[derive(Debug, Clone, Default)]
+struct ServerDetailWidget {
+    state: Arc<RwLock<ServerDetailState>>,
+}
+
+#[derive(Debug, Default)]
+struct ServerDetailState {
+    id: String,
+    detail: Option<ServerDetail>,
+    loading_state: LoadingState,
+    // shown in place of the detail when loading failed
+    error: Option<Report>,
+    scroll: u16,
+}
+
+impl ServerDetailWidget {
+    fn id(&self) -> String {
+        self.state.read().unwrap().id.clone()
+    }
+
+    fn load(&self, url: String, session: Session, id: String) {
+        {
+            let mut state = self.state.write().unwrap();
+            *state = ServerDetailState {
+                id: id.clone(),
+                loading_state: LoadingState::Loading,
+                ..Default::default()
+            };
+        }
+        let this = self.clone();
+        tokio::spawn(async move {
+            let result = show_server(url, &session, &id).await;
+            let mut state = this.state.write().unwrap();
+            // the selection moved on while this was loading
+            if state.id != id {
+                return;
+            }
+            match result {
+                Ok(detail) => {
+                    state.detail = Some(detail);
+                    state.loading_state = LoadingState::Loaded;
+                }
+                Err(err) => {
+                    state.loading_state = LoadingState::Error;
+                    state.error = Some(Report::new("Loading the server", &err));
+                }
+            }
+        });
+    }
+
+    fn scroll(&self, delta: i16) {
+        let mut state = self.state.write().unwrap();
+        state.scroll = state.scroll.saturating_add_signed(delta);
+    }
+}
+
+impl Widget for &ServerDetailWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let state = self.state.read().unwrap();
+        let title = state
+            .detail
+            .as_ref()
+            .map_or_else(|| state.id.clone(), |d| d.name.clone());
+        let loading_state = Line::from(format!("{:?}", state.loading_state)).right_aligned();
+        let block = Block::bordered()
+            .title(title)
+            .title(loading_state)
+            .title_bottom("J/K to scroll, o ports, Enter to close");
+        let lines = match (&state.detail, &state.error) {
+            (Some(detail), _) => detail.lines(),
+            (None, Some(error)) => error.lines(),
+            (None, None) => Vec::new(),
+        };
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((state.scroll, 0))
+            .render(area, buf);
+    }
+}
+
+// Nova clears the task state once an action is done; DELETED and the *_REBOOT
+// statuses can still show up without one in between
+fn is_settled(server: &Server_) -> bool {
+    server.task_state.is_none()
+        && !matches!(
+            server.status.as_str(),
+            "BUILD" | "REBOOT" | "HARD_REBOOT" | "MIGRATING" | "RESIZE" | "DELETED"
+        )
+}
+
+// OS-EXT-STS:power_state is a number from nova.compute.power_state
+fn power_state_name(state: Option<u8>) -> &'static str {
+    match state {
+        Some(0) => "NOSTATE",
+        Some(1) => "Running",
+        Some(3) => "Paused",
+        Some(4) => "Shutdown",
+        Some(6) => "Crashed",
+        Some(7) => "Suspended",
+        _ => "",
+    }
+}
+
+// The characters of `pattern` appear in `text` in order, ignoring case
+fn fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|p| chars.any(|c| c == p))
+}
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn format_remaining(remaining: chrono::TimeDelta) -> String {
+    if remaining <= chrono::TimeDelta::zero() {
+        return "Token expired".to_string();
     }
+    let minutes = remaining.num_minutes();
+    format!("Token expires in {}h {:02}m", minutes / 60, minutes % 60)
 }
 
-// token 発行
-async fn list_servers_detail(url: String) -> Result<ServersDetail> {
-    let client = Client::new();
-    let url = format!("{}/servers/detail", url);
-    let resp = client.get(&url).send().await?;
+// サーバー一覧取得
+async fn list_servers_detail(
+    url: &str,
+    session: &Session,
+    filters: &Filters,
+    sort: Option<Sort>,
+    marker: Option<&str>,
+) -> Result<ServersDetail> {
+    let mut url = reqwest::Url::parse(&format!("{}/servers/detail", url.trim_end_matches('/')))?;
+    url.query_pairs_mut()
+        .extend_pairs(filters.query())
+        .extend_pairs(sort.map(|s| s.query()).unwrap_or_default())
+        .append_pair("limit", &ServerListWidget::PAGE_LIMIT.to_string());
+    if let Some(marker) = marker {
+        url.query_pairs_mut().append_pair("marker", marker);
+    }
+    session.get_json(Category::Compute, url.as_str()).await
+}
 
-    if resp.status() != reqwest::StatusCode::OK {
-        return Err(anyhow!("Unexpected status: {}", resp.status()));
+// 単一サーバー取得 (一覧の行の更新用)
+async fn get_server(url: &str, session: &Session, id: &str) -> Result<Server_> {
+    #[derive(Deserialize)]
+    struct Body {
+        server: Server_,
     }
+    let url = format!("{}/servers/{}", url.trim_end_matches('/'), id);
+    let body: Body = session.get_json(Category::Compute, &url).await?;
+    Ok(body.server)
+}
 
-    let body = resp.json::<ServersDetail>().await?;
+// サーバー詳細取得
+async fn show_server(url: String, session: &Session, id: &str) -> Result<ServerDetail> {
+    let url = format!("{}/servers/{}", url.trim_end_matches('/'), id);
+    let body: ServerResponse = session.get_json(Category::Compute, &url).await?;
+    Ok(ServerDetail {
+        version: session.compute_version(),
+        ..body.server
+    })
+}
 
-    Ok(body)
+// サーバー操作 (POST /servers/{id}/action, 削除は DELETE /servers/{id})
+async fn server_action(url: String, session: &Session, id: &str, action: Action) -> Result<()> {
+    let url = format!("{}/servers/{}", url.trim_end_matches('/'), id);
+    // error statuses come back as ApiError from send
+    match action.body() {
+        Some(body) => {
+            let url = format!("{}/action", url);
+            session
+                .send(Category::Compute, |client| client.post(&url).json(&body))
+                .await?
+        }
+        None => {
+            session
+                .send(Category::Compute, |client| client.delete(&url))
+                .await?
+        }
+    };
+    Ok(())
 }
 
 #[derive(Deserialize, Debug)]
 struct ServersDetail {
     servers: Vec<Server_>,
+    #[serde(default)]
+    servers_links: Vec<Link>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Link {
+    href: String,
+    rel: String,
+}
+
+impl ServersDetail {
+    // Marker of the next page, from the "next" link Nova adds when it capped the result
+    fn next_marker(&self) -> Option<String> {
+        let next = self.servers_links.iter().find(|l| l.rel == "next")?;
+        reqwest::Url::parse(&next.href)
+            .ok()
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(key, _)| key == "marker")
+                    .map(|(_, value)| value.into_owned())
+            })
+            .or_else(|| self.servers.last().map(|s| s.id.clone()))
+    }
 }
 
 #[derive(Deserialize, Debug)]
 struct Server_ {
     id: String,
+    // from 2.69 servers in a down cell come back with only a few fields
+    #[serde(default)]
     name: String,
     status: String,
     #[serde(rename = "OS-EXT-STS:task_state")]
     task_state: Option<String>,
-    #[serde(rename = "OS-EXT-STS:vm_state")]
+    #[serde(rename = "OS-EXT-STS:vm_state", default)]
     vm_state: String,
+    #[serde(rename = "OS-EXT-STS:power_state", default)]
+    power_state: Option<u8>,
+    #[serde(default)]
+    flavor: Reference,
+    #[serde(default)]
+    image: Reference,
+    // network name -> addresses on it
+    #[serde(default)]
+    addresses: BTreeMap<String, Vec<Address>>,
+    #[serde(rename = "OS-EXT-AZ:availability_zone", default)]
+    availability_zone: Option<String>,
+    // only returned to admins
+    #[serde(rename = "OS-EXT-SRV-ATTR:host", default)]
+    host: Option<String>,
+    #[serde(default)]
+    key_name: Option<String>,
+    #[serde(default)]
+    created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    updated: Option<DateTime<Utc>>,
+}
+
+impl Server_ {
+    fn ips(&self) -> String {
+        self.addresses
+            .values()
+            .flatten()
+            .map(|a| a.addr.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
-impl From<&ServerState> for Row<'_> {
-    fn from(value: &ServerState) -> Self {
-        let server = value.clone();
-        Row::new(vec![server.id, server.name])
+// Flavor or image of a server. Flavors are embedded from microversion 2.47 on,
+// images are "" for servers booted from a volume
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(untagged)]
+enum Reference {
+    Embedded {
+        original_name: String,
+    },
+    Link {
+        id: String,
+    },
+    #[default]
+    #[serde(deserialize_with = "ignore")]
+    None,
+}
+
+fn ignore<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<(), D::Error> {
+    serde::de::IgnoredAny::deserialize(deserializer).map(|_| ())
+}
+
+impl Reference {
+    fn name(&self) -> String {
+        match self {
+            Reference::Embedded { original_name } => original_name.clone(),
+            Reference::Link { id } => id.clone(),
+            Reference::None => String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Address {
+    addr: String,
+    #[serde(default)]
+    version: Option<u8>,
+    // fixed or floating
+    #[serde(rename = "OS-EXT-IPS:type", default)]
+    kind: Option<String>,
+    #[serde(rename = "OS-EXT-IPS-MAC:mac_addr", default)]
+    mac_addr: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ServerResponse {
+    server: ServerDetail,
+}
+
+// GET /servers/{id}
+#[derive(Deserialize, Debug, Clone)]
+struct ServerDetail {
+    id: String,
+    name: String,
+    status: String,
+    #[serde(default)]
+    flavor: Flavor,
+    #[serde(default)]
+    image: Reference,
+    #[serde(default)]
+    addresses: BTreeMap<String, Vec<Address>>,
+    #[serde(rename = "os-extended-volumes:volumes_attached", default)]
+    volumes_attached: Vec<VolumeAttachment>,
+    #[serde(default)]
+    security_groups: Vec<SecurityGroup>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    key_name: Option<String>,
+    #[serde(default)]
+    created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    updated: Option<DateTime<Utc>>,
+    // only set for servers in ERROR
+    #[serde(default)]
+    fault: Option<Fault>,
+    // from microversion 2.19
+    #[serde(default)]
+    description: Option<String>,
+    // from microversion 2.26
+    #[serde(default)]
+    tags: Vec<String>,
+    // microversion the response was requested with, None for plain 2.1
+    #[serde(skip)]
+    version: Option<Microversion>,
+    // OS-EXT-STS:*, OS-EXT-SRV-ATTR:*, OS-EXT-AZ:* and whatever else the cloud adds
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+// Only the ID before microversion 2.47, the flavor itself from then on
+#[derive(Deserialize, Debug, Clone, Default)]
+struct Flavor {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    original_name: Option<String>,
+    #[serde(default)]
+    vcpus: Option<u32>,
+    // MiB
+    #[serde(default)]
+    ram: Option<u64>,
+    // GiB
+    #[serde(default)]
+    disk: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct VolumeAttachment {
+    id: String,
+    #[serde(default)]
+    delete_on_termination: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SecurityGroup {
+    name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Fault {
+    code: u16,
+    message: String,
+    #[serde(default)]
+    details: Option<String>,
+}
+
+impl Flavor {
+    fn describe(&self) -> String {
+        let name = self
+            .original_name
+            .clone()
+            .or_else(|| self.id.clone())
+            .unwrap_or_default();
+        match (self.vcpus, self.ram, self.disk) {
+            (Some(vcpus), Some(ram), Some(disk)) => format!(
+                "{} ({} vCPU, {} MiB RAM, {} GiB disk)",
+                name, vcpus, ram, disk
+            ),
+            _ => name,
+        }
+    }
+}
+
+impl ServerDetail {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let field = |label: &str, value: String| {
+            Line::from_iter([format!("{}: ", label).bold(), value.into()])
+        };
+        let heading = |label: &str| Line::from(label.to_string()).bold().underlined();
+
+        let image = match &self.image {
+            Reference::None => "(booted from volume)".to_string(),
+            image => image.name(),
+        };
+        let mut lines = vec![
+            field("Name", self.name.clone()),
+            field("ID", self.id.clone()),
+            field("Status", self.status.clone()),
+            field("Flavor", self.flavor.describe()),
+            field("Image", image),
+            field("Key Name", self.key_name.clone().unwrap_or_default()),
+            field("Created", format_time(self.created)),
+            field("Updated", format_time(self.updated)),
+        ];
+        // older microversions don't have these at all, rather than empty
+        let supports = |since: Microversion| self.version.is_some_and(|v| v >= since);
+        if supports(Microversion::DESCRIPTION) {
+            lines.push(field(
+                "Description",
+                self.description.clone().unwrap_or_default(),
+            ));
+        }
+        if supports(Microversion::TAGS) {
+            lines.push(field("Tags", self.tags.join(", ")));
+        }
+        if let Some(fault) = &self.fault {
+            lines.push(Line::default());
+            lines.push(heading("Fault").red());
+            lines.push(Line::from(format!("{} {}", fault.code, fault.message)).red());
+            if let Some(details) = &fault.details {
+                lines.extend(details.lines().map(|l| Line::from(l.to_string()).dim()));
+            }
+        }
+
+        lines.push(Line::default());
+        lines.push(heading("Addresses"));
+        for (network, addresses) in &self.addresses {
+            for address in addresses {
+                let mut notes: Vec<String> = Vec::new();
+                notes.extend(address.kind.clone());
+                notes.extend(address.version.map(|v| format!("IPv{}", v)));
+                notes.extend(address.mac_addr.clone());
+                lines.push(Line::from(format!(
+                    "  {}: {} ({})",
+                    network,
+                    address.addr,
+                    notes.join(", ")
+                )));
+            }
+        }
+
+        lines.push(Line::default());
+        lines.push(heading("Volumes"));
+        for volume in &self.volumes_attached {
+            let note = if volume.delete_on_termination {
+                " (deleted with the server)"
+            } else {
+                ""
+            };
+            lines.push(Line::from(format!("  {}{}", volume.id, note)));
+        }
+
+        lines.push(Line::default());
+        let groups: Vec<&str> = self
+            .security_groups
+            .iter()
+            .map(|g| g.name.as_str())
+            .collect();
+        lines.push(field("Security Groups", groups.join(", ")));
+
+        lines.push(Line::default());
+        lines.push(heading("Metadata"));
+        for (key, value) in &self.metadata {
+            lines.push(Line::from(format!("  {}: {}", key, value)));
+        }
+
+        lines.push(Line::default());
+        lines.push(heading("Extended Attributes"));
+        for (key, value) in self.extra.iter().filter(|(k, _)| k.starts_with("OS-EXT-")) {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => "-".to_string(),
+                other => other.to_string(),
+            };
+            lines.push(Line::from(format!("  {}: {}", key, value)));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn servers(ids: &[&str]) -> Vec<ServerState> {
+        ids.iter()
+            .map(|id| ServerState {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_selection_follows_server_id() {
+        let mut state = ServerListState::default();
+        state.replace_servers(servers(&["a", "b", "c"]));
+        assert_eq!(state.table_state.selected(), Some(0));
+
+        state.move_selection(2);
+        assert_eq!(state.selected_id().as_deref(), Some("c"));
+        state.move_selection(5);
+        assert_eq!(state.selected_id().as_deref(), Some("c"));
+
+        // a new server sorted in before it keeps "c" selected
+        state.replace_servers(servers(&["0", "a", "b", "c"]));
+        assert_eq!(state.selected_id().as_deref(), Some("c"));
+
+        // a deleted server leaves the selection on the same row
+        state.replace_servers(servers(&["0", "a"]));
+        assert_eq!(state.selected_id().as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_merge_highlights_changes() {
+        let mut state = ServerListState::default();
+        let now = Instant::now();
+        state.merge_servers(servers(&["a", "b"]), now);
+        state.refreshed = Some(Local::now());
+        assert!(state.servers.iter().all(|s| s.change.is_none()));
+
+        // "b" is gone, "c" is new and "a" changed status
+        let mut fresh = servers(&["a", "c"]);
+        fresh[0].status = "ACTIVE".to_string();
+        state.merge_servers(fresh, now);
+        let changes: Vec<_> = state
+            .servers
+            .iter()
+            .map(|s| (s.id.as_str(), s.change.map(|(c, _)| c)))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("a", Some(Change::Status)),
+                ("b", Some(Change::Removed)),
+                ("c", Some(Change::Added)),
+            ]
+        );
+
+        // the same list again doesn't duplicate rows
+        let mut fresh = servers(&["a", "c"]);
+        fresh[0].status = "ACTIVE".to_string();
+        state.merge_servers(fresh, now);
+        assert_eq!(state.servers.len(), 3);
+
+        state.expire_changes(now + ServerListWidget::HIGHLIGHT);
+        let ids: Vec<_> = state.servers.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert!(state.servers.iter().all(|s| s.change.is_none()));
+    }
+
+    #[test]
+    fn test_search_filters_rows() {
+        assert!(fuzzy_match("wb1", "Web-1"));
+        assert!(!fuzzy_match("1wb", "web-1"));
+
+        let mut state = ServerListState::default();
+        let mut rows = servers(&["a", "b", "c"]);
+        rows[0].name = "web-1".to_string();
+        rows[1].name = "db-1".to_string();
+        rows[1].ips = "private=10.0.0.5".to_string();
+        rows[2].name = "web-2".to_string();
+        rows[2].status = "ERROR".to_string();
+        state.replace_servers(rows);
+        state.select(2);
+
+        // the selected server stays selected while it matches
+        state.set_search("web");
+        assert_eq!(state.shown().len(), 2);
+        assert_eq!(state.selected_id().as_deref(), Some("c"));
+        state.set_search("web err");
+        assert_eq!(state.selected_id().as_deref(), Some("c"));
+        state.set_search("10.0.0");
+        assert_eq!(state.selected_id().as_deref(), Some("b"));
+        state.set_search("");
+        assert_eq!(state.shown().len(), 3);
+    }
+
+    #[test]
+    fn test_next_page_marker() {
+        let json = r#"
+        {
+            "servers": [],
+            "servers_links": [{
+                "href": "http://nova:8774/v2.1/servers/detail?limit=1&marker=a",
+                "rel": "next"
+            }]
+        }"#;
+        let page: ServersDetail = serde_json::from_str(json).unwrap();
+        assert_eq!(page.next_marker().as_deref(), Some("a"));
+
+        let last: ServersDetail = serde_json::from_str(r#"{"servers": []}"#).unwrap();
+        assert_eq!(last.next_marker(), None);
+
+        let mut state = ServerListState::default();
+        state.replace_servers(servers(&["a", "b"]));
+        state.append_servers(servers(&["b", "c"]));
+        assert_eq!(state.loaded(), 3);
+    }
+
+    #[test]
+    fn test_server_detail() {
+        let json = r#"
+        {
+            "server": {
+                "id": "978a4c30-92ef-45c5-bf65-d5624f52a295",
+                "name": "web-1",
+                "status": "ERROR",
+                "flavor": {"original_name": "m1.small", "vcpus": 1, "ram": 2048, "disk": 20},
+                "image": "",
+                "addresses": {
+                    "private": [{
+                        "addr": "10.0.0.3",
+                        "version": 4,
+                        "OS-EXT-IPS:type": "fixed",
+                        "OS-EXT-IPS-MAC:mac_addr": "fa:16:3e:00:00:01"
+                    }]
+                },
+                "os-extended-volumes:volumes_attached": [
+                    {"id": "vol-1", "delete_on_termination": true}
+                ],
+                "security_groups": [{"name": "default"}, {"name": "web"}],
+                "metadata": {"role": "frontend"},
+                "fault": {"code": 500, "message": "No valid host was found."},
+                "OS-EXT-STS:vm_state": "error",
+                "OS-EXT-STS:task_state": null,
+                "OS-EXT-SRV-ATTR:host": "compute-1",
+                "tags": []
+            }
+        }
+        "#;
+        let body: ServerResponse = serde_json::from_str(json).unwrap();
+        let text: Vec<String> = body.server.lines().iter().map(|l| l.to_string()).collect();
+        let has = |expected: &str| text.iter().any(|l| l == expected);
+        assert!(has("Flavor: m1.small (1 vCPU, 2048 MiB RAM, 20 GiB disk)"));
+        assert!(has("Image: (booted from volume)"));
+        assert!(has("500 No valid host was found."));
+        assert!(has("  private: 10.0.0.3 (fixed, IPv4, fa:16:3e:00:00:01)"));
+        assert!(has("  vol-1 (deleted with the server)"));
+        assert!(has("Security Groups: default, web"));
+        assert!(has("  role: frontend"));
+        assert!(has("  OS-EXT-SRV-ATTR:host: compute-1"));
+        assert!(has("  OS-EXT-STS:task_state: -"));
+        assert!(!text.iter().any(|l| l.contains("tags")));
+        assert!(!has("Tags: "));
+
+        // fields of later microversions are listed once they were asked for
+        let detail = ServerDetail {
+            version: Some(Microversion::TAGS),
+            ..body.server
+        };
+        let text: Vec<String> = detail.lines().iter().map(|l| l.to_string()).collect();
+        assert!(text.iter().any(|l| l == "Tags: "));
+        assert!(text.iter().any(|l| l == "Description: "));
+    }
+
+    #[test]
+    fn test_action_progress() {
+        let mut state = ServerListState::default();
+        state.replace_servers(servers(&["a", "b"]));
+        state.set_progress("b", Some("stop: powering-off (2s)".to_string()));
+        assert_eq!(
+            state.servers[1].cell(Column::TaskState),
+            "stop: powering-off (2s)"
+        );
+
+        state.select(1);
+        state.remove_server("b");
+        assert_eq!(state.selected_id().as_deref(), Some("a"));
+
+        let server: Server_ = serde_json::from_str(
+            r#"{"id": "a", "name": "a", "status": "HARD_REBOOT",
+                "OS-EXT-STS:task_state": null, "OS-EXT-STS:vm_state": "active"}"#,
+        )
+        .unwrap();
+        assert!(!is_settled(&server));
+    }
+
+    #[test]
+    fn test_action_error_keeps_loading_state() {
+        let widget = ServerListWidget::default();
+        widget.set_loading_state(LoadingState::Loaded);
+        widget.report("Stop", &anyhow::anyhow!("Conflict"));
+        assert_eq!(
+            widget.state.read().unwrap().loading_state,
+            LoadingState::Loaded
+        );
+        assert!(widget.dismiss_error());
+
+        widget.on_err("Loading servers", &anyhow::anyhow!("Unavailable"));
+        assert_eq!(
+            widget.state.read().unwrap().loading_state,
+            LoadingState::Error
+        );
+    }
+
+    #[test]
+    fn test_click_selects_row() {
+        let mut state = ServerListState {
+            area: Rect::new(0, 0, 40, 10),
+            ..Default::default()
+        };
+        state.replace_servers(servers(&["a", "b", "c"]));
+        // border on row 0, header on row 1
+        state.click(5, 3);
+        assert_eq!(state.selected_id().as_deref(), Some("b"));
+        state.click(5, 1);
+        state.click(5, 8);
+        assert_eq!(state.selected_id().as_deref(), Some("b"));
+        assert_eq!(state.page_size(), 7);
+    }
+
+    #[test]
+    fn test_sort_keeps_selection() {
+        let mut state = ServerListState {
+            area: Rect::new(0, 0, 120, 10),
+            ..Default::default()
+        };
+        let mut rows = servers(&["a", "b", "c"]);
+        for (row, name) in rows.iter_mut().zip(["web-2", "db-1", "web-1"]) {
+            row.name = name.to_string();
+        }
+        state.replace_servers(rows);
+        state.select(0);
+
+        // the header starts past the border and the ">>" highlight column
+        let name = state.columns.visible()[1];
+        assert_eq!(name.column, Column::Name);
+        let x = 1 + 2 + state.columns.visible()[0].width + 1;
+        assert_eq!(state.click(x, 1), Some(Column::Name));
+
+        state.set_sort(state.next_sort(Column::Name));
+        let names: Vec<_> = state.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["db-1", "web-1", "web-2"]);
+        assert_eq!(state.selected_id().as_deref(), Some("a"));
+
+        state.set_sort(state.next_sort(Column::Name));
+        assert_eq!(state.servers[0].name, "web-2");
+        assert_eq!(state.selected_id().as_deref(), Some("a"));
+        assert_eq!(
+            state.sort.unwrap().query(),
+            [
+                ("sort_key", "display_name".to_string()),
+                ("sort_dir", "desc".to_string())
+            ]
+        );
+        assert_eq!(Sort::label(state.sort, Column::Name), "Name ▼");
+    }
+
+    #[test]
+    fn test_server_state_from_detail() {
+        let json = r#"
+        {
+            "servers": [
+                {
+                    "id": "978a4c30-92ef-45c5-bf65-d5624f52a295",
+                    "name": "web-1",
+                    "status": "ACTIVE",
+                    "OS-EXT-STS:task_state": null,
+                    "OS-EXT-STS:vm_state": "active",
+                    "OS-EXT-STS:power_state": 1,
+                    "flavor": {"id": "1", "links": []},
+                    "image": "",
+                    "addresses": {
+                        "private": [{"addr": "10.0.0.3", "version": 4}],
+                        "public": [{"addr": "172.24.4.5", "version": 4}]
+                    },
+                    "OS-EXT-AZ:availability_zone": "nova",
+                    "key_name": null,
+                    "created": "2024-05-01T12:34:56Z"
+                },
+                {
+                    "id": "a6944d76-3bf6-4ee6-a275-f1263fae0352",
+                    "name": "db-1",
+                    "status": "BUILD",
+                    "OS-EXT-STS:task_state": "spawning",
+                    "OS-EXT-STS:vm_state": "building",
+                    "flavor": {"original_name": "m1.small", "vcpus": 1},
+                    "image": {"id": "70a599e0-31e7-49b7-b260-868f441e862b"}
+                },
+                {
+                    "id": "2f2a8c5e-0e3b-4f4d-9a6e-3c1d0b7e9f11",
+                    "status": "UNKNOWN",
+                    "links": []
+                }
+            ]
+        }
+        "#;
+        let detail: ServersDetail = serde_json::from_str(json).unwrap();
+        let web = ServerState::from(&detail.servers[0]);
+        assert_eq!(web.power_state, "Running");
+        assert_eq!(web.flavor, "1");
+        assert_eq!(web.image, "");
+        assert_eq!(web.ips, "10.0.0.3, 172.24.4.5");
+        assert_eq!(web.availability_zone, "nova");
+        assert_eq!(web.created, "2024-05-01 12:34");
+
+        let db = ServerState::from(&detail.servers[1]);
+        assert_eq!(db.task_state, "spawning");
+        assert_eq!(db.flavor, "m1.small");
+        assert_eq!(db.image, "70a599e0-31e7-49b7-b260-868f441e862b");
+        assert_eq!(db.cell(Column::Status), "BUILD");
+
+        // a server in a down cell (2.69) has little more than its ID
+        let down = ServerState::from(&detail.servers[2]);
+        assert_eq!(down.cell(Column::Status), "UNKNOWN");
+        assert_eq!(down.cell(Column::Name), "");
     }
 }
@@ -0,0 +1,172 @@
+use anyhow::{Result, anyhow};
+use chrono::TimeDelta;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::openstack::category::Category;
+use crate::openstack::error::Error;
+use crate::openstack::microversion::{Microversion, compute_headers};
+use crate::openstack::retry::{self, Progress, Retry, RetryPolicy};
+use crate::openstack::token::{
+    self, AuthReceipt, Credentials, Endpoint, TokenManager, TokenResponse,
+};
+use crate::profile::Profile;
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+// HTTP client for one profile, with the TLS settings of the profile.
+// Clones share the connection pool, so build it once and pass it around.
+pub fn client(profile: &Profile) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(TIMEOUT);
+    let ca_bundle = profile.ca_bundle.trim();
+    if !ca_bundle.is_empty() {
+        let pem = std::fs::read(ca_bundle)
+            .map_err(|e| anyhow!("Failed to read CA bundle {}: {}", ca_bundle, e))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if profile.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+// Everything an API call needs: the shared client, the token with its catalog
+// and the negotiated compute microversion
+#[derive(Debug, Clone)]
+pub struct Session {
+    client: Client,
+    tokens: TokenManager,
+    policy: RetryPolicy,
+    // the retries being waited for, for the loading state
+    retrying: Progress,
+    // negotiated with Nova when the server view opens, None means plain 2.1
+    compute_version: Arc<RwLock<Option<Microversion>>>,
+}
+
+impl Session {
+    pub fn new(
+        client: Client,
+        policy: RetryPolicy,
+        credentials: Credentials,
+        token: TokenResponse,
+    ) -> Self {
+        Self {
+            tokens: TokenManager::new(client.clone(), policy, credentials, token),
+            client,
+            policy,
+            retrying: Progress::default(),
+            compute_version: Arc::default(),
+        }
+    }
+
+    pub fn retrying(&self) -> Option<Retry> {
+        self.retrying.current()
+    }
+
+    pub fn compute_version(&self) -> Option<Microversion> {
+        *self.compute_version.read().unwrap()
+    }
+
+    pub fn set_compute_version(&self, version: Option<Microversion>) {
+        *self.compute_version.write().unwrap() = version;
+    }
+
+    pub fn remaining(&self) -> TimeDelta {
+        self.tokens.remaining()
+    }
+
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.tokens.endpoints()
+    }
+
+    // Set when MFA stopped the re-issue of the token at an auth receipt
+    pub fn receipt(&self) -> Option<AuthReceipt> {
+        self.tokens.receipt()
+    }
+
+    pub fn dismiss_receipt(&self) {
+        self.tokens.dismiss_receipt()
+    }
+
+    pub async fn verify_totp(&self, passcode: &str) -> Result<()> {
+        self.tokens.verify_totp(passcode).await
+    }
+
+    // The current token swapped for one scoped as `credentials` say
+    pub async fn rescope(&self, credentials: &Credentials) -> Result<TokenResponse> {
+        let token = self.tokens.token().await?;
+        token::rescope(&self.client, &self.policy, credentials, &token).await
+    }
+
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+
+    // Send with the current token and the headers of `service`, retried as the
+    // policy allows. The token may have been revoked or expired early, so a 401
+    // is retried once with a new one.
+    // 4xx and 5xx answers and failed connections come back as Error
+    pub async fn send<F>(&self, service: Category, request: F) -> Result<Response>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let build = |token: &str| {
+            let request = request(&self.client).header("X-Auth-Token", token);
+            match service {
+                Category::Compute => compute_headers(request, self.compute_version()),
+                _ => request,
+            }
+        };
+        let token = self.tokens.token().await?;
+        let mut resp = retry::send(
+            &self.policy,
+            service,
+            false,
+            || build(&token),
+            Some(&self.retrying),
+        )
+        .await?;
+        if resp.status() == StatusCode::UNAUTHORIZED {
+            let token = self.tokens.reauthenticate(&token).await?;
+            resp = retry::send(
+                &self.policy,
+                service,
+                false,
+                || build(&token),
+                Some(&self.retrying),
+            )
+            .await?;
+        }
+        Error::check(service, resp).await
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, service: Category, url: &str) -> Result<T> {
+        let resp = self.send(service, |client| client.get(url)).await?;
+        Ok(resp.json::<T>().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ca_bundle() {
+        let profile = Profile {
+            ca_bundle: "/nonexistent/ca.pem".to_string(),
+            ..Profile::default()
+        };
+        let error = client(&profile).unwrap_err();
+        assert!(error.to_string().contains("/nonexistent/ca.pem"));
+        assert!(client(&Profile::default()).is_ok());
+    }
+}
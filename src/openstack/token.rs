@@ -1,57 +1,410 @@
-use serde::Deserialize;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, TimeDelta, Utc};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use anyhow::{Result, anyhow};
+use std::sync::{Arc, RwLock};
 
+use crate::openstack::auth_method::AuthMethod;
 use crate::openstack::category;
+use crate::openstack::error::Error;
+use crate::openstack::interface::Interface;
+use crate::openstack::retry::{self, RetryPolicy};
+use crate::openstack::scope::Scope;
+use crate::openstack::session::Session;
 
+#[derive(Debug, Clone)]
 pub struct Endpoint {
     pub url: String,
     pub category: category::Category,
+    pub interface: Interface,
+    pub region: String,
+    pub service_name: String,
 }
 
+#[derive(Debug, Clone)]
 pub struct TokenResponse {
     pub token: String,
+    pub expires_at: DateTime<Utc>,
     pub endpoints: Vec<Endpoint>,
 }
 
-// Issue token
-pub async fn issue_token(
-    userid: String,
-    password: String,
-    tenantid: String,
-    identity_url: String,
-) -> Result<TokenResponse> {
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Credentials {
+    #[serde(rename = "auth_method")]
+    pub method: AuthMethod,
+    pub userid: String,
+    pub username: String,
+    pub user_domain_id: String,
+    pub user_domain_name: String,
+    pub password: String,
+    pub scope: Scope,
+    pub tenantid: String,
+    pub project_name: String,
+    pub project_domain_id: String,
+    pub project_domain_name: String,
+    pub domain_id: String,
+    pub domain_name: String,
+    pub application_credential_id: String,
+    pub application_credential_name: String,
+    pub application_credential_secret: String,
+    pub identity_url: String,
+}
 
-    // Build request body
-    let body = json!({
-        "auth": {
-            "identity": {
-                "methods": ["password"],
-                "password": {
-                    "user": {
-                        "id": userid,
-                        "password": password,
+impl Credentials {
+    // Whether enough is filled in to identify the user and the scope
+    pub fn is_complete(&self) -> bool {
+        let filled = |v: &str| !v.trim().is_empty();
+        let user = filled(&self.userid)
+            || (filled(&self.username)
+                && (filled(&self.user_domain_id) || filled(&self.user_domain_name)));
+        let identity = match self.method {
+            AuthMethod::Password => {
+                let scope = match self.scope {
+                    Scope::Project => {
+                        filled(&self.tenantid)
+                            || (filled(&self.project_name)
+                                && (filled(&self.project_domain_id)
+                                    || filled(&self.project_domain_name)))
+                    }
+                    Scope::Domain => filled(&self.domain_id) || filled(&self.domain_name),
+                    Scope::Unscoped => true,
+                };
+                user && filled(&self.password) && scope
+            }
+            // either the ID alone or the name together with its owner identifies the credential
+            AuthMethod::ApplicationCredential => {
+                filled(&self.application_credential_secret)
+                    && (filled(&self.application_credential_id)
+                        || (filled(&self.application_credential_name) && user))
+            }
+        };
+        identity && filled(&self.identity_url)
+    }
+}
+
+impl Credentials {
+    // Nova only answers project scoped tokens, application credentials always are
+    pub fn is_project_scoped(&self) -> bool {
+        self.method == AuthMethod::ApplicationCredential || self.scope == Scope::Project
+    }
+}
+
+// Reference a resource by ID when given, otherwise by name
+fn id_or_name(id: &str, name: &str) -> serde_json::Value {
+    if id.is_empty() {
+        json!({ "name": name })
+    } else {
+        json!({ "id": id })
+    }
+}
+
+// A user ID is unique on its own, a user name needs its domain
+fn user_json(credentials: &Credentials) -> serde_json::Value {
+    if credentials.userid.is_empty() {
+        json!({
+            "name": credentials.username,
+            "domain": id_or_name(&credentials.user_domain_id, &credentials.user_domain_name),
+        })
+    } else {
+        json!({ "id": credentials.userid })
+    }
+}
+
+fn scope_json(credentials: &Credentials) -> Option<serde_json::Value> {
+    match credentials.scope {
+        Scope::Project => {
+            let project = if credentials.tenantid.is_empty() {
+                json!({
+                    "name": credentials.project_name,
+                    "domain": id_or_name(
+                        &credentials.project_domain_id,
+                        &credentials.project_domain_name,
+                    ),
+                })
+            } else {
+                json!({ "id": credentials.tenantid })
+            };
+            Some(json!({ "project": project }))
+        }
+        Scope::Domain => Some(json!({
+            "domain": id_or_name(&credentials.domain_id, &credentials.domain_name),
+        })),
+        Scope::Unscoped => None,
+    }
+}
+
+// Build the Keystone auth request body for the selected method
+fn auth_body(credentials: &Credentials) -> serde_json::Value {
+    match credentials.method {
+        AuthMethod::Password => {
+            let mut user = user_json(credentials);
+            user["password"] = json!(credentials.password);
+            let mut body = json!({
+                "auth": {
+                    "identity": {
+                        "methods": ["password"],
+                        "password": {
+                            "user": user,
+                        }
                     }
                 }
-            },
-            "scope": {
-                "project": {
-                    "id": tenantid,
+            });
+            if let Some(scope) = scope_json(credentials) {
+                body["auth"]["scope"] = scope;
+            }
+            body
+        }
+        // application credentials are already scoped, Keystone rejects an explicit scope
+        AuthMethod::ApplicationCredential => {
+            let application_credential = if credentials.application_credential_id.is_empty() {
+                json!({
+                    "name": credentials.application_credential_name,
+                    "user": user_json(credentials),
+                    "secret": credentials.application_credential_secret,
+                })
+            } else {
+                json!({
+                    "id": credentials.application_credential_id,
+                    "secret": credentials.application_credential_secret,
+                })
+            };
+            json!({
+                "auth": {
+                    "identity": {
+                        "methods": ["application_credential"],
+                        "application_credential": application_credential,
+                    }
                 }
+            })
+        }
+    }
+}
+
+// Keystone wants more auth methods (MFA) before it issues a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthReceipt {
+    pub receipt: String,
+    pub required_methods: Vec<Vec<String>>,
+}
+
+impl AuthReceipt {
+    // Whether one of the required method sets can be completed with a TOTP passcode
+    pub fn accepts_totp(&self) -> bool {
+        self.required_methods
+            .iter()
+            .any(|methods| methods.iter().any(|m| m == "totp"))
+    }
+}
+
+impl std::fmt::Display for AuthReceipt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let methods: Vec<String> = self
+            .required_methods
+            .iter()
+            .map(|methods| methods.join("+"))
+            .collect();
+        write!(
+            f,
+            "Additional authentication required: {}",
+            methods.join(" or ")
+        )
+    }
+}
+
+impl std::error::Error for AuthReceipt {}
+
+#[derive(Deserialize)]
+struct ReceiptResponse {
+    #[serde(default)]
+    required_auth_methods: Vec<Vec<String>>,
+}
+
+// Body that completes an auth receipt with a TOTP passcode
+fn totp_body(credentials: &Credentials, passcode: &str) -> serde_json::Value {
+    let mut user = user_json(credentials);
+    user["passcode"] = json!(passcode);
+    let mut body = json!({
+        "auth": {
+            "identity": {
+                "methods": ["totp"],
+                "totp": {
+                    "user": user,
+                }
+            }
+        }
+    });
+    if let Some(scope) = scope_json(credentials) {
+        body["auth"]["scope"] = scope;
+    }
+    body
+}
+
+// clouds.yaml and OS_AUTH_URL usually include the version
+// Body that exchanges a token for one with the scope of `credentials`
+fn rescope_body(credentials: &Credentials, token: &str) -> serde_json::Value {
+    let mut body = json!({
+        "auth": {
+            "identity": {
+                "methods": ["token"],
+                "token": { "id": token },
             }
         }
     });
+    if let Some(scope) = scope_json(credentials) {
+        body["auth"]["scope"] = scope;
+    }
+    body
+}
+
+fn identity_root(identity_url: &str) -> &str {
+    identity_url
+        .trim()
+        .trim_end_matches('/')
+        .trim_end_matches("/v3")
+}
 
-    let client = Client::new();
-    let url = format!("{}/v3/auth/tokens", identity_url.trim().trim_end_matches('/'));
+#[derive(Deserialize)]
+struct VersionsResponse {
+    versions: Versions,
+}
+
+#[derive(Deserialize)]
+struct Versions {
+    values: Vec<Version>,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    id: String,
+    status: String,
+}
+
+// Version discovery at the identity root, returns the v3 version Keystone offers
+pub async fn check_identity(client: &Client, identity_url: &str) -> Result<String> {
+    let url = format!("{}/", identity_root(identity_url));
+    // Keystone answers the root with 300 Multiple Choices
     let resp = client
-        .post(&url)
-        .json(&body)
+        .get(&url)
         .send()
-        .await?;
+        .await
+        .map_err(|e| Error::from_reqwest(category::Category::Identity, e))?;
+    let resp = Error::check(category::Category::Identity, resp).await?;
+    let body = resp.json::<VersionsResponse>().await?;
+    v3_version(&body.versions.values)
+        .ok_or_else(|| anyhow!("{} does not offer the Identity v3 API", url))
+}
+
+fn v3_version(versions: &[Version]) -> Option<String> {
+    versions
+        .iter()
+        .find(|v| v.id.starts_with("v3") && v.status != "deprecated")
+        .map(|v| format!("{} ({})", v.id, v.status))
+}
+
+// Issue token
+pub async fn issue_token(
+    client: &Client,
+    policy: &RetryPolicy,
+    credentials: &Credentials,
+) -> Result<TokenResponse> {
+    // Build request body
+    let body = auth_body(credentials);
+    send_auth(client, policy, credentials, &body, None).await
+}
+
+// Finish an MFA login by sending the receipt together with a TOTP passcode
+pub async fn issue_token_with_totp(
+    client: &Client,
+    policy: &RetryPolicy,
+    credentials: &Credentials,
+    receipt: &str,
+    passcode: &str,
+) -> Result<TokenResponse> {
+    let body = totp_body(credentials, passcode);
+    send_auth(client, policy, credentials, &body, Some(receipt)).await
+}
+
+// Swap `token` for one scoped as `credentials` say, without asking for the password
+// or passcode again
+pub async fn rescope(
+    client: &Client,
+    policy: &RetryPolicy,
+    credentials: &Credentials,
+    token: &str,
+) -> Result<TokenResponse> {
+    let body = rescope_body(credentials, token);
+    send_auth(client, policy, credentials, &body, None).await
+}
+
+// A project the user has a role on, from GET /v3/auth/projects
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub domain_id: String,
+}
+
+#[derive(Deserialize)]
+struct ProjectsResponse {
+    projects: Vec<Project>,
+}
+
+pub async fn list_projects(session: &Session, identity_url: &str) -> Result<Vec<Project>> {
+    let url = format!("{}/v3/auth/projects", identity_root(identity_url));
+    let body: ProjectsResponse = session.get_json(category::Category::Identity, &url).await?;
+    Ok(body.projects)
+}
+
+async fn send_auth(
+    client: &Client,
+    policy: &RetryPolicy,
+    credentials: &Credentials,
+    body: &serde_json::Value,
+    receipt: Option<&str>,
+) -> Result<TokenResponse> {
+    let url = format!(
+        "{}/v3/auth/tokens",
+        identity_root(&credentials.identity_url)
+    );
+    let request = || {
+        let request = client.post(&url).json(body);
+        match receipt {
+            Some(receipt) => request.header("Openstack-Auth-Receipt", receipt),
+            None => request,
+        }
+    };
+    // asking again only issues another token, but a TOTP passcode may count as used
+    let idempotent = receipt.is_none();
+    let resp = retry::send(
+        policy,
+        category::Category::Identity,
+        idempotent,
+        request,
+        None,
+    )
+    .await?;
+
+    // A 401 with a receipt means the first factor was accepted
+    if resp.status() == reqwest::StatusCode::UNAUTHORIZED
+        && let Some(receipt) = resp
+            .headers()
+            .get("Openstack-Auth-Receipt")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    {
+        let body = resp.json::<ReceiptResponse>().await?;
+        return Err(AuthReceipt {
+            receipt,
+            required_methods: body.required_auth_methods,
+        }
+        .into());
+    }
 
     // Check status code
+    let resp = Error::check(category::Category::Identity, resp).await?;
     if resp.status() != reqwest::StatusCode::CREATED {
         return Err(anyhow!("Unexpected status: {}", resp.status()));
     }
@@ -68,19 +421,176 @@ pub async fn issue_token(
     let body = resp.json::<IssueTokenResponse>().await?;
 
     // Map endpoints to Endpoint struct
-    let endpoints = body.token.catalog.iter().flat_map(|cat| {
-        cat.endpoints.iter().map(move |ep| Endpoint {
-            url: ep.url.clone(),
-            category: category::Category::from_type(&cat.type_),
-        })
-    }).collect();
+    let endpoints = endpoints_from_catalog(&body.token.catalog);
 
     Ok(TokenResponse {
         token,
+        expires_at: body.token.expires_at,
         endpoints,
     })
 }
 
+// Keeps the current token and re-issues it with the saved credentials
+// when it is about to expire or has been rejected.
+// With MFA the re-issue stops at an auth receipt until a passcode completes it.
+#[derive(Debug, Clone)]
+pub struct TokenManager {
+    client: Client,
+    policy: RetryPolicy,
+    credentials: Credentials,
+    current: Arc<RwLock<TokenResponse>>,
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+    // receipt waiting for a TOTP passcode, requests fail with it meanwhile
+    receipt: Arc<RwLock<Option<AuthReceipt>>>,
+}
+
+impl TokenManager {
+    // Re-issue the token when less than this is left
+    const REFRESH_MARGIN: TimeDelta = TimeDelta::minutes(5);
+
+    pub fn new(
+        client: Client,
+        policy: RetryPolicy,
+        credentials: Credentials,
+        token: TokenResponse,
+    ) -> Self {
+        Self {
+            client,
+            policy,
+            credentials,
+            current: Arc::new(RwLock::new(token)),
+            refreshing: Arc::new(tokio::sync::Mutex::new(())),
+            receipt: Arc::default(),
+        }
+    }
+
+    // The receipt the UI should ask a passcode for, if a re-issue needs one
+    pub fn receipt(&self) -> Option<AuthReceipt> {
+        self.receipt.read().unwrap().clone()
+    }
+
+    // Forget the receipt, the next re-issue asks Keystone for a new one
+    pub fn dismiss_receipt(&self) {
+        *self.receipt.write().unwrap() = None;
+    }
+
+    // Complete the pending receipt with a TOTP passcode and use the new token
+    pub async fn verify_totp(&self, passcode: &str) -> Result<()> {
+        let receipt = self
+            .receipt()
+            .ok_or_else(|| anyhow!("No authentication is pending"))?;
+        let token = issue_token_with_totp(
+            &self.client,
+            &self.policy,
+            &self.credentials,
+            &receipt.receipt,
+            passcode,
+        )
+        .await?;
+        *self.current.write().unwrap() = token;
+        self.dismiss_receipt();
+        Ok(())
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.current.read().unwrap().expires_at
+    }
+
+    pub fn remaining(&self) -> TimeDelta {
+        self.expires_at() - Utc::now()
+    }
+
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.current.read().unwrap().endpoints.clone()
+    }
+
+    // Returns a token that is valid for at least REFRESH_MARGIN
+    pub async fn token(&self) -> Result<String> {
+        if self.remaining() < Self::REFRESH_MARGIN {
+            self.refresh(None).await
+        } else {
+            Ok(self.current.read().unwrap().token.clone())
+        }
+    }
+
+    // Re-issue the token after the API rejected `rejected` with 401
+    pub async fn reauthenticate(&self, rejected: &str) -> Result<String> {
+        self.refresh(Some(rejected)).await
+    }
+
+    async fn refresh(&self, rejected: Option<&str>) -> Result<String> {
+        let _guard = self.refreshing.lock().await;
+
+        // another task may have refreshed while we were waiting
+        {
+            let current = self.current.read().unwrap();
+            let expiring = current.expires_at - Utc::now() < Self::REFRESH_MARGIN;
+            let is_rejected = rejected == Some(current.token.as_str());
+            if !expiring && !is_rejected {
+                return Ok(current.token.clone());
+            }
+        }
+
+        // don't ask Keystone for another receipt while the user types the passcode
+        if let Some(receipt) = self.receipt() {
+            return Err(receipt.into());
+        }
+        let token = match issue_token(&self.client, &self.policy, &self.credentials).await {
+            Ok(token) => token,
+            Err(e) => {
+                if let Some(receipt) = e.downcast_ref::<AuthReceipt>()
+                    && receipt.accepts_totp()
+                {
+                    *self.receipt.write().unwrap() = Some(receipt.clone());
+                }
+                return Err(e);
+            }
+        };
+        let value = token.token.clone();
+        *self.current.write().unwrap() = token;
+        Ok(value)
+    }
+}
+
+// Find the endpoint of a service with the given interface.
+// An empty region matches endpoints in any region.
+pub fn find_endpoint<'a>(
+    endpoints: &'a [Endpoint],
+    category: category::Category,
+    interface: Interface,
+    region: &str,
+) -> Option<&'a Endpoint> {
+    endpoints.iter().find(|ep| {
+        ep.category == category
+            && ep.interface == interface
+            && (region.is_empty() || ep.region == region)
+    })
+}
+
+fn endpoints_from_catalog(catalog: &[Catalog]) -> Vec<Endpoint> {
+    catalog
+        .iter()
+        .flat_map(|cat| {
+            // skip services and interfaces we do not know about instead of guessing
+            let category = category::Category::from_type(&cat.type_);
+            cat.endpoints.iter().filter_map(move |ep| {
+                let interface = Interface::from_name(&ep.interface)?;
+                Some(Endpoint {
+                    url: ep.url.clone(),
+                    category: category?,
+                    interface,
+                    region: ep
+                        .region_id
+                        .clone()
+                        .or_else(|| ep.region.clone())
+                        .unwrap_or_default(),
+                    service_name: cat.name.clone(),
+                })
+            })
+        })
+        .collect()
+}
+
 #[derive(Deserialize)]
 struct IssueTokenResponse {
     token: Token,
@@ -88,7 +598,10 @@ struct IssueTokenResponse {
 
 #[derive(Deserialize)]
 struct Token {
+    // unscoped tokens come without a catalog
+    #[serde(default)]
     catalog: Vec<Catalog>,
+    expires_at: DateTime<Utc>,
 }
 
 #[derive(Deserialize)]
@@ -96,13 +609,16 @@ struct Catalog {
     endpoints: Vec<_Endpoint>,
     #[serde(rename = "type")]
     type_: String,
-    // name: String,
+    #[serde(default)]
+    name: String,
 }
 
 #[derive(Deserialize)]
 struct _Endpoint {
     url: String,
-    // interface: String,
+    interface: String,
+    region: Option<String>,
+    region_id: Option<String>,
 }
 
 // You can use mock by starting prisma before running tests
@@ -115,13 +631,26 @@ mod tests {
     #[tokio::test]
     async fn test_issue_token_success() {
         // Set environment variables and dummy values for testing
-        let userid = "dummy_user".to_string();
-        let password = "dummy_pass".to_string();
-        let tenantid = "dummy_tenant".to_string();
-        let identity_url = env::var("OS_IDENTITY_URL").unwrap_or_else(|_| "http://localhost:5000".to_string());
+        let credentials = Credentials {
+            userid: "dummy_user".to_string(),
+            password: "dummy_pass".to_string(),
+            tenantid: "dummy_tenant".to_string(),
+            identity_url: env::var("OS_IDENTITY_URL")
+                .unwrap_or_else(|_| "http://localhost:5000".to_string()),
+            ..Default::default()
+        };
 
         // use a mock server in the test environment or skip on failure
-        let result = issue_token(userid, password, tenantid, identity_url).await;
+        let result = issue_token(
+            &Client::new(),
+            // nothing listens there in most test environments
+            &RetryPolicy {
+                max_retries: 0,
+                ..RetryPolicy::default()
+            },
+            &credentials,
+        )
+        .await;
         match result {
             Ok(token_response) => {
                 // Check that token and endpoints are obtained
@@ -135,7 +664,238 @@ mod tests {
             }
         }
     }
-    
+
+    #[test]
+    fn test_auth_body_password() {
+        let credentials = Credentials {
+            userid: "user".to_string(),
+            password: "pass".to_string(),
+            tenantid: "project".to_string(),
+            ..Default::default()
+        };
+        let body = auth_body(&credentials);
+        assert_eq!(body["auth"]["identity"]["methods"], json!(["password"]));
+        assert_eq!(body["auth"]["identity"]["password"]["user"]["id"], "user");
+        assert_eq!(body["auth"]["scope"]["project"]["id"], "project");
+    }
+
+    #[test]
+    fn test_auth_body_user_name_and_project_name() {
+        let credentials = Credentials {
+            username: "alice".to_string(),
+            user_domain_name: "Default".to_string(),
+            password: "pass".to_string(),
+            project_name: "demo".to_string(),
+            project_domain_id: "default".to_string(),
+            ..Default::default()
+        };
+        let body = auth_body(&credentials);
+        let user = &body["auth"]["identity"]["password"]["user"];
+        assert_eq!(user["name"], "alice");
+        assert_eq!(user["domain"], json!({ "name": "Default" }));
+        assert_eq!(user["password"], "pass");
+        assert!(user.get("id").is_none());
+        assert_eq!(
+            body["auth"]["scope"],
+            json!({ "project": { "name": "demo", "domain": { "id": "default" } } })
+        );
+    }
+
+    #[test]
+    fn test_auth_body_domain_and_unscoped() {
+        let domain_scoped = Credentials {
+            userid: "user".to_string(),
+            password: "pass".to_string(),
+            scope: Scope::Domain,
+            domain_name: "Default".to_string(),
+            ..Default::default()
+        };
+        let body = auth_body(&domain_scoped);
+        assert_eq!(
+            body["auth"]["scope"],
+            json!({ "domain": { "name": "Default" } })
+        );
+
+        let unscoped = Credentials {
+            scope: Scope::Unscoped,
+            ..domain_scoped
+        };
+        let body = auth_body(&unscoped);
+        assert!(body["auth"].get("scope").is_none());
+    }
+
+    #[test]
+    fn test_credentials_is_complete() {
+        let by_name = Credentials {
+            username: "alice".to_string(),
+            password: "pass".to_string(),
+            project_name: "demo".to_string(),
+            project_domain_name: "Default".to_string(),
+            identity_url: "http://localhost:5000".to_string(),
+            ..Default::default()
+        };
+        // a user name is ambiguous without its domain
+        assert!(!by_name.is_complete());
+
+        let by_name = Credentials {
+            user_domain_name: "Default".to_string(),
+            ..by_name
+        };
+        assert!(by_name.is_complete());
+
+        let domain_scoped = Credentials {
+            scope: Scope::Domain,
+            ..by_name.clone()
+        };
+        assert!(!domain_scoped.is_complete());
+
+        let unscoped = Credentials {
+            scope: Scope::Unscoped,
+            ..by_name
+        };
+        assert!(unscoped.is_complete());
+    }
+
+    #[test]
+    fn test_identity_root() {
+        assert_eq!(
+            identity_root("http://keystone:5000/v3/"),
+            "http://keystone:5000"
+        );
+        assert_eq!(
+            identity_root(" https://cloud/identity "),
+            "https://cloud/identity"
+        );
+    }
+
+    #[test]
+    fn test_version_discovery() {
+        let json = r#"
+        {
+            "versions": {
+                "values": [
+                    {"id": "v2.0", "status": "deprecated", "links": []},
+                    {"id": "v3.14", "status": "stable", "links": []}
+                ]
+            }
+        }
+        "#;
+        let body: VersionsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            v3_version(&body.versions.values).as_deref(),
+            Some("v3.14 (stable)")
+        );
+        assert_eq!(v3_version(&body.versions.values[..1]), None);
+    }
+
+    #[test]
+    fn test_totp_body() {
+        let credentials = Credentials {
+            username: "alice".to_string(),
+            user_domain_id: "default".to_string(),
+            password: "pass".to_string(),
+            tenantid: "project".to_string(),
+            ..Default::default()
+        };
+        let body = totp_body(&credentials, "123456");
+        let identity = &body["auth"]["identity"];
+        assert_eq!(identity["methods"], json!(["totp"]));
+        assert_eq!(identity["totp"]["user"]["name"], "alice");
+        assert_eq!(identity["totp"]["user"]["passcode"], "123456");
+        // the password was already accepted with the receipt
+        assert!(identity.get("password").is_none());
+        assert_eq!(body["auth"]["scope"]["project"]["id"], "project");
+    }
+
+    #[test]
+    fn test_rescope_body() {
+        let credentials = Credentials {
+            tenantid: "project".to_string(),
+            ..Default::default()
+        };
+        let body = rescope_body(&credentials, "unscoped_token");
+        let identity = &body["auth"]["identity"];
+        assert_eq!(identity["methods"], json!(["token"]));
+        assert_eq!(identity["token"]["id"], "unscoped_token");
+        assert_eq!(body["auth"]["scope"]["project"]["id"], "project");
+
+        assert!(credentials.is_project_scoped());
+        let unscoped = Credentials {
+            scope: Scope::Unscoped,
+            ..credentials
+        };
+        assert!(!unscoped.is_project_scoped());
+    }
+
+    #[test]
+    fn test_projects_response() {
+        let json = r#"
+        {
+            "projects": [
+                {"id": "263fd9", "name": "dev", "domain_id": "default", "enabled": true},
+                {"id": "50ef01", "name": "prod"}
+            ],
+            "links": {"self": "http://localhost:5000/v3/auth/projects"}
+        }
+        "#;
+        let body: ProjectsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(body.projects.len(), 2);
+        assert_eq!(body.projects[0].name, "dev");
+        assert_eq!(body.projects[1].domain_id, "");
+    }
+
+    #[test]
+    fn test_receipt_response() {
+        let json = r#"
+        {
+            "receipt": {
+                "methods": ["password"],
+                "expires_at": "2015-08-27T09:49:58.000000Z"
+            },
+            "required_auth_methods": [["password", "totp"]]
+        }
+        "#;
+        let body: ReceiptResponse = serde_json::from_str(json).unwrap();
+        let receipt = AuthReceipt {
+            receipt: "receipt".to_string(),
+            required_methods: body.required_auth_methods,
+        };
+        assert!(receipt.accepts_totp());
+        assert_eq!(
+            receipt.to_string(),
+            "Additional authentication required: password+totp"
+        );
+    }
+
+    #[test]
+    fn test_auth_body_application_credential() {
+        let by_id = Credentials {
+            method: AuthMethod::ApplicationCredential,
+            application_credential_id: "appcred".to_string(),
+            application_credential_secret: "secret".to_string(),
+            ..Default::default()
+        };
+        let body = auth_body(&by_id);
+        let identity = &body["auth"]["identity"];
+        assert_eq!(identity["methods"], json!(["application_credential"]));
+        assert_eq!(identity["application_credential"]["id"], "appcred");
+        assert_eq!(identity["application_credential"]["secret"], "secret");
+        assert!(body["auth"].get("scope").is_none());
+
+        let by_name = Credentials {
+            method: AuthMethod::ApplicationCredential,
+            userid: "user".to_string(),
+            application_credential_name: "ci".to_string(),
+            application_credential_secret: "secret".to_string(),
+            ..Default::default()
+        };
+        let body = auth_body(&by_name);
+        let application_credential = &body["auth"]["identity"]["application_credential"];
+        assert_eq!(application_credential["name"], "ci");
+        assert_eq!(application_credential["user"]["id"], "user");
+        assert!(application_credential.get("id").is_none());
+    }
+
     #[test]
     fn test_tokenresponse_deserialize() {
         // Dummy response JSON
@@ -145,34 +905,216 @@ mod tests {
                 "catalog": [
                     {
                         "endpoints": [
-                            { "url": "http://example.com" }
+                            { "url": "http://example.com", "interface": "public", "region_id": "RegionOne" }
                         ],
-                        "type": "compute"
+                        "type": "compute",
+                        "name": "nova"
                     }
-                ]
+                ],
+                "expires_at": "2015-08-27T09:49:58.000000Z"
             }
         }
         "#;
 
         // Deserialize to IssueTokenResponse
-        let issue_token_resp: IssueTokenResponse = serde_json::from_str(json).expect("deserialize IssueTokenResponse");
+        let issue_token_resp: IssueTokenResponse =
+            serde_json::from_str(json).expect("deserialize IssueTokenResponse");
 
         // Convert to TokenResponse
-        let endpoints: Vec<Endpoint> = issue_token_resp.token.catalog.iter().flat_map(|cat| {
-            cat.endpoints.iter().map(move |ep| Endpoint {
-                url: ep.url.clone(),
-                category: category::Category::from_type(&cat.type_),
-            })
-        }).collect();
-
         let token_response = TokenResponse {
             token: "dummy_token".to_string(),
-            endpoints,
+            expires_at: issue_token_resp.token.expires_at,
+            endpoints: endpoints_from_catalog(&issue_token_resp.token.catalog),
         };
 
         // Check TokenResponse contents
         assert_eq!(token_response.token, "dummy_token");
         assert_eq!(token_response.endpoints.len(), 1);
         assert_eq!(token_response.endpoints[0].url, "http://example.com");
+        assert_eq!(token_response.endpoints[0].interface, Interface::Public);
+        assert_eq!(token_response.endpoints[0].region, "RegionOne");
+        assert_eq!(token_response.endpoints[0].service_name, "nova");
+        assert_eq!(
+            token_response.expires_at.to_rfc3339(),
+            "2015-08-27T09:49:58+00:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_manager_keeps_valid_token() {
+        let manager = TokenManager::new(
+            Client::new(),
+            RetryPolicy::default(),
+            Credentials::default(),
+            TokenResponse {
+                token: "valid_token".to_string(),
+                expires_at: Utc::now() + TimeDelta::hours(1),
+                endpoints: Vec::new(),
+            },
+        );
+
+        assert!(manager.remaining() > TimeDelta::minutes(59));
+        assert_eq!(manager.token().await.unwrap(), "valid_token");
+        // a 401 for an older token must not trigger another re-issue
+        assert_eq!(
+            manager.reauthenticate("stale_token").await.unwrap(),
+            "valid_token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_manager_waits_for_passcode() {
+        let manager = TokenManager::new(
+            Client::new(),
+            RetryPolicy::default(),
+            Credentials::default(),
+            TokenResponse {
+                token: "expired_token".to_string(),
+                expires_at: Utc::now(),
+                endpoints: Vec::new(),
+            },
+        );
+        let receipt = AuthReceipt {
+            receipt: "receipt".to_string(),
+            required_methods: vec![vec!["password".to_string(), "totp".to_string()]],
+        };
+        *manager.receipt.write().unwrap() = Some(receipt.clone());
+
+        // requests fail with the pending receipt instead of asking Keystone again
+        let err = manager.token().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<AuthReceipt>(), Some(&receipt));
+        assert_eq!(manager.receipt(), Some(receipt));
+
+        manager.dismiss_receipt();
+        assert_eq!(manager.receipt(), None);
+        assert!(manager.verify_totp("123456").await.is_err());
+    }
+
+    #[test]
+    fn test_multi_region_catalog() {
+        // Two regions, three interfaces each, plus a legacy "region" only endpoint
+        // and a service this client does not use
+        let json = r#"
+        {
+            "token": {
+                "catalog": [
+                    {
+                        "endpoints": [
+                            { "url": "https://nova.r1.example.com/v2.1", "interface": "public", "region_id": "RegionOne", "region": "RegionOne" },
+                            { "url": "http://nova.r1.internal/v2.1", "interface": "internal", "region_id": "RegionOne", "region": "RegionOne" },
+                            { "url": "http://nova.r1.admin/v2.1", "interface": "admin", "region_id": "RegionOne", "region": "RegionOne" },
+                            { "url": "https://nova.r2.example.com/v2.1", "interface": "public", "region_id": "RegionTwo", "region": "RegionTwo" },
+                            { "url": "http://nova.r2.internal/v2.1", "interface": "internal", "region_id": "RegionTwo", "region": "RegionTwo" },
+                            { "url": "http://nova.r2.admin/v2.1", "interface": "admin", "region_id": "RegionTwo", "region": "RegionTwo" }
+                        ],
+                        "type": "compute",
+                        "name": "nova"
+                    },
+                    {
+                        "endpoints": [
+                            { "url": "https://cinder.example.com/v3", "interface": "public", "region": "RegionOne" }
+                        ],
+                        "type": "volumev3",
+                        "name": "cinderv3"
+                    },
+                    {
+                        "endpoints": [
+                            { "url": "https://keystone.example.com/v3", "interface": "public", "region": "RegionOne" }
+                        ],
+                        "type": "identity",
+                        "name": "keystone"
+                    }
+                ],
+                "expires_at": "2015-08-27T09:49:58.000000Z"
+            }
+        }
+        "#;
+
+        let resp: IssueTokenResponse =
+            serde_json::from_str(json).expect("deserialize IssueTokenResponse");
+        let endpoints = endpoints_from_catalog(&resp.token.catalog);
+        assert_eq!(endpoints.len(), 7);
+
+        let find = |interface, region| {
+            find_endpoint(&endpoints, category::Category::Compute, interface, region)
+                .map(|ep| ep.url.as_str())
+        };
+        assert_eq!(
+            find(Interface::Public, "RegionTwo"),
+            Some("https://nova.r2.example.com/v2.1")
+        );
+        assert_eq!(
+            find(Interface::Internal, "RegionOne"),
+            Some("http://nova.r1.internal/v2.1")
+        );
+        assert_eq!(
+            find(Interface::Admin, "RegionTwo"),
+            Some("http://nova.r2.admin/v2.1")
+        );
+        // empty region picks the first match in catalog order
+        assert_eq!(
+            find(Interface::Public, ""),
+            Some("https://nova.r1.example.com/v2.1")
+        );
+        assert_eq!(find(Interface::Public, "RegionThree"), None);
+
+        let identity = find_endpoint(
+            &endpoints,
+            category::Category::Identity,
+            Interface::Public,
+            "RegionOne",
+        )
+        .expect("identity endpoint");
+        assert_eq!(identity.service_name, "keystone");
+    }
+
+    #[test]
+    fn test_find_endpoint() {
+        let endpoint = |url: &str, category, interface| Endpoint {
+            url: url.to_string(),
+            category,
+            interface,
+            region: "RegionOne".to_string(),
+            service_name: String::new(),
+        };
+        let endpoints = vec![
+            endpoint(
+                "http://identity.example.com",
+                category::Category::Identity,
+                Interface::Public,
+            ),
+            endpoint(
+                "http://compute.internal",
+                category::Category::Compute,
+                Interface::Internal,
+            ),
+            endpoint(
+                "http://compute.example.com",
+                category::Category::Compute,
+                Interface::Public,
+            ),
+        ];
+
+        let compute = find_endpoint(
+            &endpoints,
+            category::Category::Compute,
+            Interface::Public,
+            "RegionOne",
+        );
+        assert_eq!(
+            compute.map(|ep| ep.url.as_str()),
+            Some("http://compute.example.com")
+        );
+
+        let identity_only = &endpoints[..1];
+        assert!(
+            find_endpoint(
+                identity_only,
+                category::Category::Compute,
+                Interface::Public,
+                ""
+            )
+            .is_none()
+        );
     }
 }
@@ -0,0 +1,578 @@
+use color_eyre::eyre::{Result, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, Paragraph};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config};
+use crate::input::TextInput;
+use crate::openstack::column::Columns;
+use crate::openstack::interface::Interface;
+use crate::openstack::microversion::Microversion;
+use crate::openstack::retry::RetryPolicy;
+use crate::openstack::token::Credentials;
+use crate::secret::{self, SecretStorage, SecretStore};
+use crate::state;
+
+// A named set of credentials and endpoint preferences
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub credentials: Credentials,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub interface: Interface,
+    #[serde(default)]
+    pub secret_storage: SecretStorage,
+    #[serde(default)]
+    pub columns: Columns,
+    // seconds between server list refreshes, 0 turns it off
+    #[serde(default = "default_refresh_interval")]
+    pub refresh_interval: u64,
+    // highest Nova microversion to negotiate, "2.79"
+    #[serde(default = "default_max_microversion")]
+    pub max_microversion: String,
+    // PEM file with extra CA certificates, for clouds behind a private CA
+    #[serde(default)]
+    pub ca_bundle: String,
+    // skip TLS certificate verification
+    #[serde(default)]
+    pub insecure: bool,
+    // retries of a request that failed with 409, 429, 503 or a timeout, 0 turns it off
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    // first backoff in milliseconds, doubled on every retry
+    #[serde(default = "default_retry_delay")]
+    pub retry_delay: u64,
+}
+
+pub const DEFAULT_REFRESH_INTERVAL: u64 = 30;
+
+fn default_refresh_interval() -> u64 {
+    DEFAULT_REFRESH_INTERVAL
+}
+
+fn default_max_retries() -> u32 {
+    RetryPolicy::default().max_retries
+}
+
+fn default_retry_delay() -> u64 {
+    RetryPolicy::default().base_delay.as_millis() as u64
+}
+
+fn default_max_microversion() -> String {
+    Microversion::DEFAULT_MAX.to_string()
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            credentials: Credentials::default(),
+            region: String::new(),
+            interface: Interface::default(),
+            secret_storage: SecretStorage::default(),
+            columns: Columns::default(),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            max_microversion: default_max_microversion(),
+            ca_bundle: String::new(),
+            insecure: false,
+            max_retries: default_max_retries(),
+            retry_delay: default_retry_delay(),
+        }
+    }
+}
+
+impl Profile {
+    // Copy of the profile as it may be written to profiles.json
+    fn for_storage(&self) -> Profile {
+        let mut profile = self.clone();
+        if profile.secret_storage != SecretStorage::Plain {
+            profile.credentials.password.clear();
+            profile.credentials.application_credential_secret.clear();
+        }
+        profile
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Store {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("ratatui-sample"))
+}
+
+impl Store {
+    // Read profiles.json, importing the old single-profile config.json the first time
+    pub fn load() -> Result<Self> {
+        match config_dir() {
+            Some(dir) => Self::load_from(&dir),
+            None => Ok(Store::default()),
+        }
+    }
+
+    fn load_from(dir: &Path) -> Result<Self> {
+        let path = dir.join("profiles.json");
+        if path.exists() {
+            let content = secret::read_private(&path)?;
+            return Ok(serde_json::from_str(&content)?);
+        }
+
+        let legacy = dir.join("config.json");
+        if !legacy.exists() {
+            return Ok(Store::default());
+        }
+        // older versions wrote it world-readable, so it is only imported once the
+        // user has run chmod on it, like any other file holding a password
+        let mut profile: Profile = serde_json::from_str(&secret::read_private(&legacy)?)?;
+        profile.name = "default".to_string();
+        let store = Store {
+            profiles: vec![profile],
+        };
+        store.save_to(dir)?;
+        std::fs::remove_file(&legacy)?;
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = config_dir().ok_or_else(|| eyre!("No config directory"))?;
+        self.save_to(&dir)
+    }
+
+    fn save_to(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let store = Store {
+            profiles: self.profiles.iter().map(Profile::for_storage).collect(),
+        };
+        let content = serde_json::to_string_pretty(&store)?;
+        secret::write_private(&dir.join("profiles.json"), &content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    // Insert or replace a profile; `original` is its name before editing
+    pub fn upsert(&mut self, original: Option<&str>, profile: Profile) -> Result<()> {
+        if original != Some(profile.name.as_str()) && self.get(&profile.name).is_some() {
+            return Err(eyre!("A profile named {} already exists", profile.name));
+        }
+        match original.and_then(|name| self.profiles.iter().position(|p| p.name == name)) {
+            Some(index) => self.profiles[index] = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+
+    // Keep the server table layout of a stored profile
+    pub fn save_columns(name: &str, columns: &Columns) -> Result<()> {
+        let mut store = Store::load()?;
+        let profile = store
+            .profiles
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| {
+                eyre!(
+                    "Profile {} is not saved, columns apply to this session only",
+                    name
+                )
+            })?;
+        profile.columns = columns.clone();
+        store.save()
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+    }
+
+    // First free "<name> (copy)" style name
+    pub fn copy_name(&self, name: &str) -> String {
+        let mut candidate = format!("{} (copy)", name);
+        let mut n = 2;
+        while self.get(&candidate).is_some() {
+            candidate = format!("{} (copy {})", name, n);
+            n += 1;
+        }
+        candidate
+    }
+}
+
+// Startup screen listing saved profiles and the one from clouds.yaml / OS_* variables
+pub struct ProfilePicker {
+    store: Store,
+    environment: Option<Config>,
+    list_state: ListState,
+    confirm_delete: bool,
+    // asked for after confirming the delete of an encrypted profile, to drop its secrets.enc entry
+    passphrase: Option<TextInput>,
+    message: String,
+    // form handed over to the app when leaving the picker
+    selected: Option<Config>,
+}
+
+impl ProfilePicker {
+    pub fn load() -> Self {
+        // printing would be hidden by the alternate screen, so show them here
+        let (environment, errors) = config::from_environment();
+        let mut picker = Self {
+            store: Store::default(),
+            environment,
+            list_state: ListState::default(),
+            confirm_delete: false,
+            passphrase: None,
+            message: errors.join("; "),
+            selected: None,
+        };
+        picker.reload();
+        picker
+    }
+
+    // Re-read the store after a profile was saved from the config form
+    pub fn reload(&mut self) {
+        let current = self.current_name();
+        self.store = match Store::load() {
+            Ok(store) => store,
+            Err(e) => {
+                self.message = format!("Error loading profiles: {}", e);
+                Store::default()
+            }
+        };
+        let index = current
+            .and_then(|name| self.names().iter().position(|n| *n == name))
+            .unwrap_or(0);
+        self.list_state
+            .select((!self.names().is_empty()).then_some(index));
+        self.confirm_delete = false;
+        self.passphrase = None;
+    }
+
+    pub fn take_selected(&mut self) -> Option<Config> {
+        self.selected.take()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.environment
+            .iter()
+            .map(|c| c.name())
+            .chain(self.store.profiles.iter().map(|p| p.name.clone()))
+            .collect()
+    }
+
+    fn current_name(&self) -> Option<String> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.names().get(i).cloned())
+    }
+
+    // The environment entry comes first and is not stored
+    fn current(&self) -> Option<Config> {
+        let index = self.list_state.selected()?;
+        let offset = usize::from(self.environment.is_some());
+        if index < offset {
+            return self.environment.clone();
+        }
+        self.store.profiles.get(index - offset).map(Config::from)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [message_area, list_area] = frame.area().layout(&layout);
+
+        if let Some(passphrase) = &self.passphrase {
+            let label = "Passphrase for secrets.enc: ";
+            let layout =
+                Layout::horizontal([Constraint::Length(label.len() as u16), Constraint::Fill(1)]);
+            let [label_area, value_area] = message_area.layout(&layout);
+            frame.render_widget(Line::from(label).bold(), label_area);
+            frame.render_widget(passphrase, value_area);
+            let x = value_area.x + passphrase.cursor_x(value_area.width);
+            frame.set_cursor_position((x, value_area.y));
+        } else {
+            let message = if self.confirm_delete {
+                format!(
+                    "Delete profile {}? (y/n)",
+                    self.current_name().unwrap_or_default()
+                )
+            } else {
+                self.message.clone()
+            };
+            frame.render_widget(Paragraph::new(message), message_area);
+        }
+
+        let env_items = self.environment.iter().map(|config| {
+            let profile = config.profile();
+            ListItem::new(Line::from_iter([
+                profile.name.clone().bold(),
+                format!("  {}  [environment]", profile.credentials.identity_url).dim(),
+            ]))
+        });
+        let items = self.store.profiles.iter().map(|profile| {
+            ListItem::new(Line::from_iter([
+                profile.name.clone().bold(),
+                format!("  {}", profile.credentials.identity_url).dim(),
+            ]))
+        });
+        let list = List::new(env_items.chain(items).collect::<Vec<_>>())
+            .block(
+                Block::bordered()
+                    .title("Profiles")
+                    .title_bottom("Enter connect, a add, e edit, c clone, d delete, Esc quit"),
+            )
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+            .highlight_style(Style::new().on_blue());
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+    }
+
+    pub fn handle_events(&mut self, event: Option<KeyEvent>) -> state::AppState {
+        let Some(key) = event else {
+            return state::AppState::Profiles;
+        };
+
+        if let Some(passphrase) = &mut self.passphrase {
+            match key.code {
+                KeyCode::Esc => self.passphrase = None,
+                KeyCode::Enter => {
+                    let passphrase = passphrase.value().to_string();
+                    self.delete(Some(&passphrase));
+                }
+                _ => {
+                    passphrase.on_key_press(key);
+                }
+            }
+            return state::AppState::Profiles;
+        }
+
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            if key.code == KeyCode::Char('y') {
+                let encrypted = self
+                    .current()
+                    .is_some_and(|c| c.profile().secret_storage == SecretStorage::Encrypted);
+                if encrypted {
+                    self.passphrase = Some(TextInput::masked());
+                } else {
+                    self.delete(None);
+                }
+            }
+            return state::AppState::Profiles;
+        }
+
+        self.message.clear();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return state::AppState::Quit,
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::Enter => {
+                if let Some(config) = self.current() {
+                    let next = if config.is_valid() {
+                        state::AppState::IssueToken(Box::new(config.profile()))
+                    } else {
+                        // incomplete profiles open in the form first
+                        state::AppState::Loading
+                    };
+                    self.selected = Some(config);
+                    return next;
+                }
+            }
+            KeyCode::Char('a') => {
+                self.selected = Some(Config::default());
+                return state::AppState::Loading;
+            }
+            KeyCode::Char('e') => {
+                if let Some(config) = self.current() {
+                    self.selected = Some(config);
+                    return state::AppState::Loading;
+                }
+            }
+            KeyCode::Char('c') => {
+                if let Some(config) = self.current() {
+                    let mut profile = config.profile();
+                    profile.name = self.store.copy_name(&profile.name);
+                    self.selected = Some(Config::new_profile(&profile));
+                    return state::AppState::Loading;
+                }
+            }
+            KeyCode::Char('d') => match self.current() {
+                Some(config) if config.is_stored() => self.confirm_delete = true,
+                Some(_) => self.message = "The environment profile cannot be deleted".to_string(),
+                None => {}
+            },
+            _ => {}
+        }
+        state::AppState::Profiles
+    }
+
+    // Remove the current profile, and its secrets.enc entry when a passphrase is given
+    fn delete(&mut self, passphrase: Option<&str>) {
+        let Some(name) = self.current_name() else {
+            return;
+        };
+        self.passphrase = None;
+        if let Some(passphrase) = passphrase
+            && let Err(e) = remove_secrets(&name, passphrase)
+        {
+            // the profile stays so its secrets are not left behind
+            self.message = format!("Error removing secrets, profile kept: {}", e);
+            return;
+        }
+        self.store.remove(&name);
+        if let Err(e) = self.store.save() {
+            self.message = format!("Error saving profiles: {}", e);
+        }
+        self.reload();
+    }
+}
+
+fn remove_secrets(name: &str, passphrase: &str) -> Result<()> {
+    let mut secrets = SecretStore::unlock(passphrase)?;
+    if secrets.profiles.remove(name).is_some() {
+        secrets.save(passphrase)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_store_upsert() {
+        let mut store = Store::default();
+        store.upsert(None, profile("dev")).unwrap();
+        store.upsert(None, profile("prod")).unwrap();
+        assert!(store.upsert(None, profile("dev")).is_err());
+
+        // renaming replaces the original entry in place
+        store.upsert(Some("dev"), profile("staging")).unwrap();
+        let names: Vec<_> = store.profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["staging", "prod"]);
+
+        // but must not take over another profile's name
+        assert!(store.upsert(Some("staging"), profile("prod")).is_err());
+
+        store.remove("prod");
+        assert_eq!(store.profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_delete_encrypted_profile_asks_passphrase() {
+        let mut encrypted = profile("prod");
+        encrypted.secret_storage = SecretStorage::Encrypted;
+        let mut picker = ProfilePicker {
+            store: Store {
+                profiles: vec![encrypted],
+            },
+            environment: None,
+            list_state: ListState::default().with_selected(Some(0)),
+            confirm_delete: false,
+            passphrase: None,
+            message: String::new(),
+            selected: None,
+        };
+        let key = |code| Some(KeyEvent::from(code));
+
+        picker.handle_events(key(KeyCode::Char('d')));
+        picker.handle_events(key(KeyCode::Char('y')));
+        assert!(picker.passphrase.is_some());
+        picker.handle_events(key(KeyCode::Char('x')));
+        assert_eq!(picker.passphrase.as_ref().unwrap().value(), "x");
+
+        // Esc cancels the delete and keeps the profile
+        picker.handle_events(key(KeyCode::Esc));
+        assert!(picker.passphrase.is_none());
+        assert_eq!(picker.store.profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_store_copy_name() {
+        let mut store = Store::default();
+        store.upsert(None, profile("dev")).unwrap();
+        assert_eq!(store.copy_name("dev"), "dev (copy)");
+        store.upsert(None, profile("dev (copy)")).unwrap();
+        assert_eq!(store.copy_name("dev"), "dev (copy 2)");
+    }
+
+    #[test]
+    fn test_profile_for_storage() {
+        let mut profile = profile("dev");
+        profile.credentials.password = "pass".to_string();
+        assert_eq!(profile.for_storage().credentials.password, "pass");
+
+        profile.secret_storage = SecretStorage::Prompt;
+        assert_eq!(profile.for_storage().credentials.password, "");
+        profile.secret_storage = SecretStorage::Encrypted;
+        assert_eq!(profile.for_storage().credentials.password, "");
+    }
+
+    #[test]
+    fn test_profile_reads_legacy_config() {
+        let json = r#"{
+            "userid": "user",
+            "password": "pass",
+            "tenantid": "project",
+            "identity_url": "http://localhost:5000"
+        }"#;
+        let profile: Profile = serde_json::from_str(json).unwrap();
+        assert_eq!(profile.name, "");
+        assert_eq!(profile.credentials.userid, "user");
+        assert_eq!(profile.interface, Interface::Public);
+        assert_eq!(profile.secret_storage, SecretStorage::Plain);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_store_migrates_legacy_config() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("ratatui-sample-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("config.json");
+        std::fs::write(
+            &legacy,
+            r#"{"userid": "user", "password": "pass", "identity_url": "http://localhost:5000"}"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&legacy, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        // a world-readable config.json is refused and left alone
+        let err = Store::load_from(&dir).unwrap_err();
+        assert!(err.to_string().contains("chmod 600"));
+        assert!(legacy.exists());
+        assert!(!dir.join("profiles.json").exists());
+
+        // once it is private it is imported, then removed
+        std::fs::set_permissions(&legacy, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let store = Store::load_from(&dir).unwrap();
+        assert_eq!(store.profiles[0].name, "default");
+        assert_eq!(store.profiles[0].credentials.password, "pass");
+        assert!(!legacy.exists());
+        let mode = std::fs::metadata(dir.join("profiles.json"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // and read back from profiles.json from then on
+        let store = Store::load_from(&dir).unwrap();
+        assert_eq!(store.profiles[0].credentials.userid, "user");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
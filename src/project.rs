@@ -0,0 +1,71 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, HighlightSpacing, List, ListItem, ListState, Paragraph};
+
+use crate::openstack::token::Project;
+use crate::state;
+
+// Shown after an unscoped or domain scoped login, Nova needs a project scoped token
+pub struct ProjectPicker {
+    projects: Vec<Project>,
+    list_state: ListState,
+    message: String,
+}
+
+impl ProjectPicker {
+    pub fn new(projects: Vec<Project>, message: String) -> Self {
+        Self {
+            projects,
+            list_state: ListState::default().with_selected(Some(0)),
+            message,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Project> {
+        // select_next only stops at the last item when the list is rendered
+        let last = self.projects.len().checked_sub(1)?;
+        let index = self.list_state.selected()?.min(last);
+        self.projects.get(index)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let layout = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]);
+        let [message_area, list_area] = frame.area().layout(&layout);
+
+        frame.render_widget(Paragraph::new(self.message.clone()), message_area);
+        let items = self.projects.iter().map(|project| {
+            ListItem::new(Line::from_iter([
+                project.name.clone().bold(),
+                format!("  {}  {}", project.id, project.domain_id).dim(),
+            ]))
+        });
+        let list = List::new(items.collect::<Vec<_>>())
+            .block(
+                Block::bordered()
+                    .title("Projects")
+                    .title_bottom("Enter use project, Esc back"),
+            )
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+            .highlight_style(Style::new().on_blue());
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+    }
+
+    pub fn handle_events(&mut self, event: Option<KeyEvent>) -> state::AppState {
+        let Some(key) = event else {
+            return state::AppState::Projects;
+        };
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return state::AppState::Loading,
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::Enter if self.selected().is_some() => return state::AppState::Rescope,
+            _ => {}
+        }
+        state::AppState::Projects
+    }
+}
@@ -0,0 +1,216 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use color_eyre::eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Where the password and application credential secret of a profile are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretStorage {
+    // in profiles.json, readable only by the owner
+    #[default]
+    Plain,
+    // never saved, asked for on every connect
+    Prompt,
+    // in secrets.enc, unlocked by a passphrase
+    Encrypted,
+}
+
+impl SecretStorage {
+    pub const ALL: [SecretStorage; 3] = [
+        SecretStorage::Plain,
+        SecretStorage::Prompt,
+        SecretStorage::Encrypted,
+    ];
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            SecretStorage::Plain => "plain",
+            SecretStorage::Prompt => "prompt",
+            SecretStorage::Encrypted => "encrypted",
+        }
+    }
+}
+
+// Write a file only the owner can read
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // the mode above only applies to new files
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(content.as_bytes())?;
+    }
+    #[cfg(not(unix))]
+    std::fs::write(path, content)?;
+
+    Ok(())
+}
+
+// Read a file holding credentials, refusing it when the group or others have any access,
+// like ssh does for private keys
+pub fn read_private(path: &Path) -> Result<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(eyre!(
+                "{} is accessible by others (mode {:o}), run `chmod 600 {}` first",
+                path.display(),
+                mode & 0o777,
+                path.display()
+            ));
+        }
+    }
+
+    Ok(std::fs::read_to_string(path)?)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Secrets {
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub application_credential_secret: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// Secrets of all encrypted profiles, keyed by profile name
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SecretStore {
+    pub profiles: HashMap<String, Secrets>,
+}
+
+fn secrets_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("ratatui-sample/secrets.enc"))
+}
+
+impl SecretStore {
+    // Decrypt secrets.enc, an empty store when it does not exist yet
+    pub fn unlock(passphrase: &str) -> Result<Self> {
+        let path = secrets_path().ok_or_else(|| eyre!("No config directory"))?;
+        if !path.exists() {
+            return Ok(SecretStore::default());
+        }
+        let plaintext = decrypt(passphrase, &read_private(&path)?)?;
+        Ok(serde_json::from_str(&plaintext)?)
+    }
+
+    pub fn save(&self, passphrase: &str) -> Result<()> {
+        let path = secrets_path().ok_or_else(|| eyre!("No config directory"))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = encrypt(passphrase, &serde_json::to_string(self)?)?;
+        write_private(&path, &content)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| eyre!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt(passphrase: &str, plaintext: &str) -> Result<String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| eyre!("Failed to encrypt secrets"))?;
+
+    let file = EncryptedFile {
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    };
+    Ok(serde_json::to_string(&file)?)
+}
+
+fn decrypt(passphrase: &str, content: &str) -> Result<String> {
+    let file: EncryptedFile = serde_json::from_str(content)?;
+    let salt = from_hex(&file.salt)?;
+    let nonce = from_hex(&file.nonce)?;
+    if nonce.len() != 12 {
+        return Err(eyre!("Corrupted secrets file"));
+    }
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            from_hex(&file.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| eyre!("Wrong passphrase"))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(eyre!("Invalid hex string"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| eyre!(e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let content = encrypt("passphrase", "secret value").unwrap();
+        assert!(!content.contains("secret value"));
+        assert_eq!(decrypt("passphrase", &content).unwrap(), "secret value");
+        assert!(decrypt("wrong", &content).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("ratatui-sample-{}", std::process::id()));
+        write_private(&path, "{}").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(read_private(&path).unwrap(), "{}");
+
+        for mode in [0o644, 0o640, 0o620, 0o610, 0o602] {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+            assert!(read_private(&path).is_err());
+        }
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o400)).unwrap();
+        assert_eq!(read_private(&path).unwrap(), "{}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,14 +1,16 @@
+use crate::profile::Profile;
 
 #[derive(Default, PartialEq, Eq)]
 pub enum AppState {
     #[default]
+    Profiles,
     Loading,
-    IssueToken {
-        userid: String,
-        password: String,
-        tenantid: String,
-        identity_url: String,
-    },
+    TestConnection(String),
+    IssueToken(Box<Profile>),
+    Totp,
+    VerifyTotp,
+    Projects,
+    Rescope,
     Server,
     Quit,
 }
@@ -0,0 +1,92 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+use ratatui_core::style::Stylize;
+
+use crate::input::TextInput;
+use crate::openstack::token::AuthReceipt;
+use crate::profile::Profile;
+use crate::state;
+
+const LABEL: &str = "TOTP Passcode";
+
+// Second factor prompt shown after Keystone returns an auth receipt
+pub struct TotpPrompt {
+    pub profile: Profile,
+    pub receipt: AuthReceipt,
+    pub passcode: TextInput,
+    pub message: String,
+}
+
+impl TotpPrompt {
+    pub fn new(profile: Profile, receipt: AuthReceipt) -> Self {
+        Self {
+            profile,
+            receipt,
+            passcode: TextInput::new(),
+            message: String::new(),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ]);
+        let [message_area, title_area, input_area, help_area] = frame.area().layout(&layout);
+
+        frame.render_widget(Paragraph::new(self.message.clone()), message_area);
+        let title = format!("Multi-factor authentication for {}", self.profile.name);
+        frame.render_widget(Line::from(title).bold(), title_area);
+        let layout = Layout::horizontal([
+            Constraint::Length(LABEL.len() as u16 + 2),
+            Constraint::Fill(1),
+        ]);
+        let [label_area, value_area] = input_area.layout(&layout);
+        frame.render_widget(Line::from(format!("{}: ", LABEL)).bold(), label_area);
+        frame.render_widget(&self.passcode, value_area);
+        frame.render_widget(
+            Line::from("Enter to verify, Esc to cancel").dim(),
+            help_area,
+        );
+        let x = value_area.x + self.passcode.cursor_x(value_area.width);
+        frame.set_cursor_position((x, value_area.y));
+    }
+
+    pub fn handle_events(&mut self, event: Option<KeyEvent>) -> state::AppState {
+        let Some(key) = event else {
+            return state::AppState::Totp;
+        };
+
+        match key.code {
+            KeyCode::Esc => return state::AppState::Loading,
+            KeyCode::Enter => {
+                if self.passcode.is_empty() {
+                    self.message = "Please enter the passcode.".to_string();
+                } else {
+                    return state::AppState::VerifyTotp;
+                }
+            }
+            // passcodes are digits only, editing chords still go to the input
+            KeyCode::Char(c)
+                if !c.is_ascii_digit()
+                    && !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+            _ => {
+                self.passcode.on_key_press(key);
+            }
+        }
+        state::AppState::Totp
+    }
+
+    // Authenticator apps often copy the code as "123 456"
+    pub fn paste(&mut self, text: &str) {
+        let digits: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+        self.passcode.paste(&digits);
+    }
+}